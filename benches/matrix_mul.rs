@@ -0,0 +1,51 @@
+use std::{hint::black_box, time::Instant};
+
+use matrix_renderer::math::matrices::{Matrix4, Vector4};
+
+const ITERATIONS: usize = 1_000_000;
+
+fn bench(name: &str, mut f: impl FnMut()) -> f64 {
+    // warm up
+    for _ in 0..ITERATIONS / 10 {
+        f();
+    }
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        f();
+    }
+    let ns = start.elapsed().as_nanos() as f64 / ITERATIONS as f64;
+    println!("{name:<24} {ns:>8.2} ns/iter");
+    ns
+}
+
+fn main() {
+    let mut x = 0.0_f32;
+    let a = Matrix4::<f32>::generate(|| {
+        x += 0.37;
+        x.sin()
+    });
+    let b = Matrix4::<f32>::generate(|| {
+        x += 0.91;
+        x.cos()
+    });
+    let v = Vector4::<f32>::generate(|| {
+        x += 0.53;
+        x.sin()
+    });
+
+    let generic = bench("generic matrix * matrix", || {
+        black_box(black_box(&a).mul_matrix(black_box(&b)));
+    });
+    let simd = bench("simd matrix * matrix", || {
+        black_box(black_box(&a).mul_matrix4(black_box(&b)));
+    });
+    println!("speedup: {:.2}x", generic / simd);
+
+    let generic = bench("generic matrix * vector", || {
+        black_box(black_box(&a).mul_matrix(black_box(&v)));
+    });
+    let simd = bench("simd matrix * vector", || {
+        black_box(black_box(&a).mul_vector4(black_box(&v)));
+    });
+    println!("speedup: {:.2}x", generic / simd);
+}
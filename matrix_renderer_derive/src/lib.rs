@@ -0,0 +1,166 @@
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{format_ident, quote};
+use syn::{
+    parse_macro_input, spanned::Spanned, Attribute, Data, DeriveInput, Error, Expr, ExprLit, Ident,
+    Lit, LitInt, Type,
+};
+
+// every field becomes one attribute, or one per column for `[[T; N]; M]`. fields without
+// `#[location(n)]` take the location after the previous field, starting at 0. the offsets are
+// the sums of the field sizes, which is the real layout since `Pod` rules out padding
+#[proc_macro_derive(Bufferable, attributes(location, step_mode))]
+pub fn derive_bufferable(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    if !input.generics.params.is_empty() {
+        return Err(Error::new(
+            input.generics.span(),
+            "`Bufferable` can't be derived for generic types",
+        ));
+    }
+    let Data::Struct(data) = &input.data else {
+        return Err(Error::new(
+            Span::call_site(),
+            "`Bufferable` can only be derived for structs",
+        ));
+    };
+    let step_mode = step_mode(&input.attrs)?;
+
+    let mut attributes = Vec::new();
+    let mut used = Vec::new();
+    let mut next = 0;
+    let mut offset = quote!(0);
+    for field in data.fields.iter() {
+        let ty = &field.ty;
+        let (format, columns) = field_format(ty)?;
+        let start = location(&field.attrs)?.unwrap_or(next);
+        for column in 0..columns {
+            let location = start + column;
+            if used.contains(&location) {
+                return Err(Error::new(
+                    field.span(),
+                    format!("location {location} is used by another field"),
+                ));
+            }
+            used.push(location);
+            let column = column as usize;
+            let columns = columns as usize;
+            attributes.push(quote! {
+                ::matrix_renderer::wgpu::VertexAttribute {
+                    format: ::matrix_renderer::wgpu::VertexFormat::#format,
+                    offset: (#offset + ::std::mem::size_of::<#ty>() / #columns * #column)
+                        as ::matrix_renderer::wgpu::BufferAddress,
+                    shader_location: #location,
+                }
+            });
+        }
+        next = start + columns;
+        offset = quote!(#offset + ::std::mem::size_of::<#ty>());
+    }
+
+    Ok(quote! {
+        impl ::matrix_renderer::pipelines::buffers::Bufferable for #name {
+            fn describe<'a>() -> ::matrix_renderer::wgpu::VertexBufferLayout<'a> {
+                const ATTRS: &[::matrix_renderer::wgpu::VertexAttribute] = &[#(#attributes),*];
+                ::matrix_renderer::wgpu::VertexBufferLayout {
+                    array_stride: ::std::mem::size_of::<#name>()
+                        as ::matrix_renderer::wgpu::BufferAddress,
+                    step_mode: ::matrix_renderer::wgpu::VertexStepMode::#step_mode,
+                    attributes: ATTRS,
+                }
+            }
+        }
+    })
+}
+
+fn step_mode(attrs: &[Attribute]) -> syn::Result<Ident> {
+    let Some(attr) = attrs.iter().find(|x| x.path().is_ident("step_mode")) else {
+        return Ok(format_ident!("Vertex"));
+    };
+    let mode = attr.parse_args::<Ident>()?;
+    match mode.to_string().as_str() {
+        "vertex" => Ok(format_ident!("Vertex")),
+        "instance" => Ok(format_ident!("Instance")),
+        _ => Err(Error::new(
+            mode.span(),
+            "expected `#[step_mode(vertex)]` or `#[step_mode(instance)]`",
+        )),
+    }
+}
+
+fn location(attrs: &[Attribute]) -> syn::Result<Option<u32>> {
+    attrs
+        .iter()
+        .find(|x| x.path().is_ident("location"))
+        .map(|attr| attr.parse_args::<LitInt>()?.base10_parse())
+        .transpose()
+}
+
+// the vertex format of one column and the number of columns
+fn field_format(ty: &Type) -> syn::Result<(Ident, u32)> {
+    let unsupported = || {
+        Error::new(
+            ty.span(),
+            format!(
+                "`{}` is not a supported vertex attribute type, expected a scalar, \
+                 `[T; N]` or `[[T; N]; M]` of f32, f64, u32, i32, u16, i16, u8 or i8",
+                quote!(#ty)
+            ),
+        )
+    };
+    let format = match ty {
+        Type::Array(array) => match array_len(&array.len) {
+            Some(len) => match &*array.elem {
+                Type::Array(column) => {
+                    let rows = array_len(&column.len).ok_or_else(unsupported)?;
+                    let format = vector_format(&column.elem, rows).ok_or_else(unsupported)?;
+                    return Ok((format, len));
+                }
+                elem => vector_format(elem, len),
+            },
+            None => None,
+        },
+        ty => vector_format(ty, 1),
+    };
+    format.map(|x| (x, 1)).ok_or_else(unsupported)
+}
+
+fn vector_format(ty: &Type, len: u32) -> Option<Ident> {
+    let Type::Path(path) = ty else {
+        return None;
+    };
+    let scalar = path.path.get_ident()?.to_string();
+    let (name, small) = match scalar.as_str() {
+        "f32" => ("Float32", false),
+        "f64" => ("Float64", false),
+        "u32" => ("Uint32", false),
+        "i32" => ("Sint32", false),
+        "u16" => ("Uint16", true),
+        "i16" => ("Sint16", true),
+        "u8" => ("Uint8", true),
+        "i8" => ("Sint8", true),
+        _ => return None,
+    };
+    // 8 and 16 bit formats only come in pairs and quads
+    match (len, small) {
+        (1, false) => Some(format_ident!("{name}")),
+        (2..=4, false) | (2 | 4, true) => Some(format_ident!("{name}x{len}")),
+        _ => None,
+    }
+}
+
+fn array_len(len: &Expr) -> Option<u32> {
+    match len {
+        Expr::Lit(ExprLit {
+            lit: Lit::Int(len), ..
+        }) => len.base10_parse().ok(),
+        _ => None,
+    }
+}
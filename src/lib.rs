@@ -1,5 +1,10 @@
 #![allow(dead_code)]
 
+// lets `#[derive(Bufferable)]` name this crate from inside it
+extern crate self as matrix_renderer;
+
+pub use wgpu;
+
 pub mod math;
 pub mod renderer;
 pub mod pipelines;
\ No newline at end of file
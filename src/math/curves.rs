@@ -0,0 +1,322 @@
+use std::f32::consts::PI;
+
+use num_traits::Float;
+
+use super::{
+    matrices::{Matrix, Vector3},
+    quaternions::Quaternion,
+    vectors::{Vector, Vector3D},
+};
+
+pub fn lerp<T: Float>(a: T, b: T, t: T) -> T {
+    a + (b - a) * t
+}
+
+impl<T: Float, const N: usize, const M: usize> Matrix<T, N, M> {
+    pub fn lerp(&self, other: &Self, t: T) -> Self {
+        self.map(|pos, x| lerp(x, other[pos], t))
+    }
+}
+
+impl Vector3<f32> {
+    // rotates the direction along the great circle and interpolates the length linearly,
+    // opposite directions turn around an arbitrary perpendicular axis
+    pub fn slerp(&self, other: &Self, t: f32) -> Self {
+        let (from_len, to_len) = (self.dot(self).sqrt(), other.dot(other).sqrt());
+        if from_len == 0.0 || to_len == 0.0 {
+            return self.lerp(other, t);
+        }
+        let (from, to) = (self / from_len, other / to_len);
+        let cos = from.dot(&to).clamp(-1.0, 1.0);
+        let len = lerp(from_len, to_len, t);
+
+        // nearly parallel, the sines below would divide by almost 0
+        if cos > 0.9995 {
+            return from.lerp(&to, t).normalized() * len;
+        }
+        if cos < -0.9995 {
+            let mut axis = from.cross(&Vector3::from([[1.0, 0.0, 0.0]]));
+            if axis.dot(&axis) < 1e-6 {
+                axis = from.cross(&Vector3::from([[0.0, 1.0, 0.0]]));
+            }
+            return Quaternion::from_axis_angle(&axis, PI * t).rotate_vector(&from) * len;
+        }
+
+        let theta = cos.acos();
+        let sin = theta.sin();
+        (from * (((1.0 - t) * theta).sin() / sin) + to * ((t * theta).sin() / sin)) * len
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Easing {
+    #[default]
+    Linear,
+    QuadIn,
+    QuadOut,
+    QuadInOut,
+    CubicIn,
+    CubicOut,
+    CubicInOut,
+    SineIn,
+    SineOut,
+    SineInOut,
+    SmoothStep,
+}
+
+impl Easing {
+    // maps 0..1 to 0..1, `t` is clamped
+    pub fn apply(&self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::QuadIn => t * t,
+            Easing::QuadOut => 1.0 - (1.0 - t) * (1.0 - t),
+            Easing::QuadInOut => {
+                if t < 0.5 {
+                    2.0 * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(2) / 2.0
+                }
+            }
+            Easing::CubicIn => t * t * t,
+            Easing::CubicOut => 1.0 - (1.0 - t).powi(3),
+            Easing::CubicInOut => {
+                if t < 0.5 {
+                    4.0 * t * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+                }
+            }
+            Easing::SineIn => 1.0 - (t * PI / 2.0).cos(),
+            Easing::SineOut => (t * PI / 2.0).sin(),
+            Easing::SineInOut => -((PI * t).cos() - 1.0) / 2.0,
+            Easing::SmoothStep => t * t * (3.0 - 2.0 * t),
+        }
+    }
+}
+
+pub trait Curve {
+    // `t` goes from 0 at the start of the curve to 1 at its end
+    fn sample(&self, t: f32) -> Vector3<f32>;
+}
+
+// a bezier curve of any degree, evaluated with de Casteljau
+pub struct Bezier {
+    points: Vec<Vector3<f32>>,
+}
+
+impl Bezier {
+    pub fn new(points: Vec<Vector3<f32>>) -> Self {
+        assert!(
+            !points.is_empty(),
+            "a bezier curve needs at least one point"
+        );
+        Self { points }
+    }
+
+    pub fn points(&self) -> &[Vector3<f32>] {
+        &self.points
+    }
+}
+
+impl Curve for Bezier {
+    fn sample(&self, t: f32) -> Vector3<f32> {
+        let t = t.clamp(0.0, 1.0);
+        let mut points = self.points.clone();
+        for len in (1..points.len()).rev() {
+            for i in 0..len {
+                points[i] = points[i].lerp(&points[i + 1], t);
+            }
+        }
+        points.swap_remove(0)
+    }
+}
+
+pub struct Hermite {
+    pub start: Vector3<f32>,
+    pub start_tangent: Vector3<f32>,
+    pub end: Vector3<f32>,
+    pub end_tangent: Vector3<f32>,
+}
+
+impl Curve for Hermite {
+    fn sample(&self, t: f32) -> Vector3<f32> {
+        let t2 = t * t;
+        let t3 = t2 * t;
+        &self.start * (2.0 * t3 - 3.0 * t2 + 1.0)
+            + &self.start_tangent * (t3 - 2.0 * t2 + t)
+            + &self.end * (-2.0 * t3 + 3.0 * t2)
+            + &self.end_tangent * (t3 - t2)
+    }
+}
+
+// a uniform catmull-rom spline that passes through all of its points
+pub struct CatmullRom {
+    points: Vec<Vector3<f32>>,
+}
+
+impl CatmullRom {
+    pub fn new(points: Vec<Vector3<f32>>) -> Self {
+        assert!(
+            points.len() >= 2,
+            "a catmull-rom spline needs at least two points"
+        );
+        Self { points }
+    }
+
+    pub fn points(&self) -> &[Vector3<f32>] {
+        &self.points
+    }
+
+    fn segment(&self, i: usize) -> Hermite {
+        let last = self.points.len() - 1;
+        let p = |i: usize| &self.points[i.min(last)];
+        let (p0, p1, p2, p3) = (p(i.saturating_sub(1)), p(i), p(i + 1), p(i + 2));
+        Hermite {
+            start: p1.clone(),
+            start_tangent: (p2 - p0) * 0.5,
+            end: p2.clone(),
+            end_tangent: (p3 - p1) * 0.5,
+        }
+    }
+}
+
+impl Curve for CatmullRom {
+    fn sample(&self, t: f32) -> Vector3<f32> {
+        let segments = self.points.len() - 1;
+        let t = t.clamp(0.0, 1.0) * segments as f32;
+        let i = (t.floor() as usize).min(segments - 1);
+        self.segment(i).sample(t - i as f32)
+    }
+}
+
+// reparameterizes a curve by distance so it is traversed at constant speed
+pub struct ArcLength<C: Curve> {
+    curve: C,
+    // the accumulated length at `i / (lengths.len() - 1)`
+    lengths: Vec<f32>,
+}
+
+impl<C: Curve> ArcLength<C> {
+    pub fn new(curve: C, samples: usize) -> Self {
+        let samples = samples.max(1);
+        let mut lengths = Vec::with_capacity(samples + 1);
+        lengths.push(0.0);
+        let mut prev = curve.sample(0.0);
+        let mut total = 0.0;
+        for i in 1..=samples {
+            let current = curve.sample(i as f32 / samples as f32);
+            let delta = &current - &prev;
+            total += delta.dot(&delta).sqrt();
+            lengths.push(total);
+            prev = current;
+        }
+        Self { curve, lengths }
+    }
+
+    pub fn curve(&self) -> &C {
+        &self.curve
+    }
+
+    pub fn length(&self) -> f32 {
+        *self.lengths.last().unwrap()
+    }
+
+    // the curve parameter at `distance` along the curve
+    pub fn parameter_at(&self, distance: f32) -> f32 {
+        let distance = distance.clamp(0.0, self.length());
+        let i = self
+            .lengths
+            .partition_point(|x| *x < distance)
+            .clamp(1, self.lengths.len() - 1);
+        let (a, b) = (self.lengths[i - 1], self.lengths[i]);
+        let local = if b > a { (distance - a) / (b - a) } else { 0.0 };
+        (i as f32 - 1.0 + local) / (self.lengths.len() - 1) as f32
+    }
+
+    pub fn sample_at_distance(&self, distance: f32) -> Vector3<f32> {
+        self.curve.sample(self.parameter_at(distance))
+    }
+}
+
+impl<C: Curve> Curve for ArcLength<C> {
+    fn sample(&self, t: f32) -> Vector3<f32> {
+        self.sample_at_distance(t * self.length())
+    }
+}
+
+#[test]
+fn test_curves() {
+    let close = |a: &Vector3<f32>, b: &Vector3<f32>, eps: f32| {
+        a.iter().all(|(pos, x)| (x - b[pos]).abs() < eps)
+    };
+    let points: Vec<Vector3<f32>> = vec![
+        [[0.0, 0.0, 0.0]].into(),
+        [[1.0, 2.0, 0.0]].into(),
+        [[3.0, 2.0, 1.0]].into(),
+        [[4.0, 0.0, 0.0]].into(),
+    ];
+
+    let bezier = Bezier::new(points.clone());
+    assert!(close(&bezier.sample(0.0), &points[0], 1e-6));
+    assert!(close(&bezier.sample(1.0), &points[3], 1e-6));
+    assert!(close(&bezier.sample(1.5), &points[3], 1e-6));
+    assert!(close(&bezier.sample(-1.0), &points[0], 1e-6));
+    // the cubic bernstein form at 0.5
+    let mid = (&points[0] + &points[3]) * 0.125 + (&points[1] + &points[2]) * 0.375;
+    assert!(close(&bezier.sample(0.5), &mid, 1e-6));
+
+    let spline = CatmullRom::new(points.clone());
+    for (i, p) in points.iter().enumerate() {
+        assert!(close(&spline.sample(i as f32 / 3.0), p, 1e-5));
+    }
+
+    let line = ArcLength::new(
+        Bezier::new(vec![
+            [[0.0, 0.0, 0.0]].into(),
+            [[0.1, 0.0, 0.0]].into(),
+            [[10.0, 0.0, 0.0]].into(),
+        ]),
+        1000,
+    );
+    assert!((line.length() - 10.0).abs() < 1e-3);
+    assert!((line.sample(0.25)[0] - 2.5).abs() < 1e-2);
+
+    for easing in [
+        Easing::Linear,
+        Easing::CubicInOut,
+        Easing::SineOut,
+        Easing::SmoothStep,
+    ] {
+        assert_eq!(easing.apply(0.0), 0.0);
+        assert!((easing.apply(1.0) - 1.0).abs() < 1e-6);
+    }
+}
+
+#[test]
+fn test_vector_slerp() {
+    let close =
+        |a: &Vector3<f32>, b: &Vector3<f32>| a.iter().all(|(pos, x)| (x - b[pos]).abs() < 1e-4);
+    let x: Vector3<f32> = [[2.0, 0.0, 0.0]].into();
+    let y: Vector3<f32> = [[0.0, 4.0, 0.0]].into();
+
+    let half = x.slerp(&y, 0.5);
+    let diagonal = 3.0 * std::f32::consts::FRAC_1_SQRT_2;
+    assert!(close(&half, &[[diagonal, diagonal, 0.0]].into()));
+    assert!(close(&x.slerp(&y, 0.0), &x));
+    assert!(close(&x.slerp(&y, 1.0), &y));
+
+    // nearly parallel falls back to a normalized lerp
+    let almost: Vector3<f32> = [[2.0, 0.001, 0.0]].into();
+    let mid = x.slerp(&almost, 0.5);
+    assert!((mid.dot(&mid).sqrt() - 2.0).abs() < 1e-4);
+    assert!(mid[1] > 0.0 && mid[1] < 0.001);
+
+    // antiparallel turns around a perpendicular axis and keeps the length
+    let back: Vector3<f32> = [[-2.0, 0.0, 0.0]].into();
+    let quarter = x.slerp(&back, 0.5);
+    assert!(quarter[0].abs() < 1e-4);
+    assert!((quarter.dot(&quarter).sqrt() - 2.0).abs() < 1e-4);
+    assert!(close(&x.slerp(&back, 1.0), &back));
+}
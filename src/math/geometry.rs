@@ -0,0 +1,443 @@
+use super::{
+    matrices::{Matrix4, Vector3, Vector4},
+    vectors::{Vector, Vector3D},
+};
+
+fn vec3(x: f32, y: f32, z: f32) -> Vector3<f32> {
+    Vector3::from([[x, y, z]])
+}
+
+fn transform_point(m: &Matrix4<f32>, p: &Vector3<f32>) -> Vector3<f32> {
+    let v = m * Vector4::from([[*p.x(), *p.y(), *p.z(), 1.0]]);
+    match v[3] == 0.0 || v[3] == 1.0 {
+        true => vec3(v[0], v[1], v[2]),
+        false => vec3(v[0] / v[3], v[1] / v[3], v[2] / v[3]),
+    }
+}
+
+fn transform_vector(m: &Matrix4<f32>, d: &Vector3<f32>) -> Vector3<f32> {
+    let v = m * Vector4::from([[*d.x(), *d.y(), *d.z(), 0.0]]);
+    vec3(v[0], v[1], v[2])
+}
+
+fn length(v: &Vector3<f32>) -> f32 {
+    v.dot(v).sqrt()
+}
+
+#[derive(Debug, Clone)]
+pub struct Aabb {
+    pub min: Vector3<f32>,
+    pub max: Vector3<f32>,
+}
+
+impl Aabb {
+    pub fn new(min: Vector3<f32>, max: Vector3<f32>) -> Self {
+        Self { min, max }
+    }
+
+    // an inverted box that becomes valid after the first expand or merge
+    pub fn empty() -> Self {
+        Self {
+            min: vec3(f32::INFINITY, f32::INFINITY, f32::INFINITY),
+            max: vec3(f32::NEG_INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY),
+        }
+    }
+
+    pub fn from_points<'a>(points: impl IntoIterator<Item = &'a Vector3<f32>>) -> Self {
+        let mut ans = Self::empty();
+        for p in points {
+            ans.expand_to_point(p);
+        }
+        ans
+    }
+
+    pub fn is_empty(&self) -> bool {
+        (0..3).any(|i| self.min[i] > self.max[i])
+    }
+
+    pub fn center(&self) -> Vector3<f32> {
+        (&self.min + &self.max) * 0.5
+    }
+
+    pub fn half_extents(&self) -> Vector3<f32> {
+        (&self.max - &self.min) * 0.5
+    }
+
+    pub fn corners(&self) -> [Vector3<f32>; 8] {
+        let (a, b) = (&self.min, &self.max);
+        [
+            vec3(*a.x(), *a.y(), *a.z()),
+            vec3(*b.x(), *a.y(), *a.z()),
+            vec3(*a.x(), *b.y(), *a.z()),
+            vec3(*b.x(), *b.y(), *a.z()),
+            vec3(*a.x(), *a.y(), *b.z()),
+            vec3(*b.x(), *a.y(), *b.z()),
+            vec3(*a.x(), *b.y(), *b.z()),
+            vec3(*b.x(), *b.y(), *b.z()),
+        ]
+    }
+
+    pub fn contains_point(&self, p: &Vector3<f32>) -> bool {
+        (0..3).all(|i| self.min[i] <= p[i] && p[i] <= self.max[i])
+    }
+
+    pub fn intersects(&self, other: &Aabb) -> bool {
+        (0..3).all(|i| self.min[i] <= other.max[i] && other.min[i] <= self.max[i])
+    }
+
+    pub fn expand_to_point(&mut self, p: &Vector3<f32>) {
+        for i in 0..3 {
+            self.min[i] = self.min[i].min(p[i]);
+            self.max[i] = self.max[i].max(p[i]);
+        }
+    }
+
+    pub fn expand(&self, amount: f32) -> Self {
+        let amount = vec3(amount, amount, amount);
+        Self::new(&self.min - &amount, &self.max + &amount)
+    }
+
+    pub fn merge(&self, other: &Aabb) -> Self {
+        let mut ans = self.clone();
+        ans.expand_to_point(&other.min);
+        ans.expand_to_point(&other.max);
+        ans
+    }
+
+    pub fn transform(&self, m: &Matrix4<f32>) -> Self {
+        if self.is_empty() {
+            return self.clone();
+        }
+        Self::from_points(&self.corners().map(|c| transform_point(m, &c)))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Sphere {
+    pub center: Vector3<f32>,
+    pub radius: f32,
+}
+
+impl Sphere {
+    pub fn new(center: Vector3<f32>, radius: f32) -> Self {
+        Self { center, radius }
+    }
+
+    pub fn contains_point(&self, p: &Vector3<f32>) -> bool {
+        let d = p - &self.center;
+        d.dot(&d) <= self.radius * self.radius
+    }
+
+    pub fn intersects(&self, other: &Sphere) -> bool {
+        let d = &other.center - &self.center;
+        let r = self.radius + other.radius;
+        d.dot(&d) <= r * r
+    }
+
+    pub fn expand(&self, amount: f32) -> Self {
+        Self::new(self.center.clone(), self.radius + amount)
+    }
+
+    pub fn merge(&self, other: &Sphere) -> Self {
+        let d = &other.center - &self.center;
+        let dist = length(&d);
+
+        if dist + other.radius <= self.radius {
+            return self.clone();
+        }
+        if dist + self.radius <= other.radius {
+            return other.clone();
+        }
+
+        let radius = (dist + self.radius + other.radius) * 0.5;
+        let center = &self.center + d * ((radius - self.radius) / dist);
+        Self::new(center, radius)
+    }
+
+    pub fn transform(&self, m: &Matrix4<f32>) -> Self {
+        let scale = (0..3)
+            .map(|c| length(&vec3(m[(c, 0)], m[(c, 1)], m[(c, 2)])))
+            .fold(0.0, f32::max);
+
+        Self::new(transform_point(m, &self.center), self.radius * scale)
+    }
+}
+
+// the points where `normal.dot(p) + distance == 0`, the normal points to the positive side
+#[derive(Debug, Clone)]
+pub struct Plane {
+    pub normal: Vector3<f32>,
+    pub distance: f32,
+}
+
+impl Plane {
+    pub fn new(normal: Vector3<f32>, distance: f32) -> Self {
+        Self { normal, distance }
+    }
+
+    pub fn from_point_normal(point: &Vector3<f32>, normal: &Vector3<f32>) -> Self {
+        let normal = normal.normalized();
+        Self::new(normal.clone(), -normal.dot(point))
+    }
+
+    // counter clockwise points face the positive side
+    pub fn from_points(a: &Vector3<f32>, b: &Vector3<f32>, c: &Vector3<f32>) -> Self {
+        Self::from_point_normal(a, &(b - a).cross(&(c - a)))
+    }
+
+    pub fn normalized(&self) -> Self {
+        let len = length(&self.normal);
+        match len == 0.0 {
+            true => self.clone(),
+            false => Self::new(&self.normal / len, self.distance / len),
+        }
+    }
+
+    pub fn signed_distance(&self, p: &Vector3<f32>) -> f32 {
+        self.normal.dot(p) + self.distance
+    }
+
+    pub fn transform(&self, m: &Matrix4<f32>) -> Option<Self> {
+        let inv = m.inverse()?;
+        let p = Vector4::from([[
+            *self.normal.x(),
+            *self.normal.y(),
+            *self.normal.z(),
+            self.distance,
+        ]]);
+        let column =
+            |c: usize| Vector4::from([[inv[(c, 0)], inv[(c, 1)], inv[(c, 2)], inv[(c, 3)]]]);
+
+        Some(
+            Self::new(
+                vec3(column(0).dot(&p), column(1).dot(&p), column(2).dot(&p)),
+                column(3).dot(&p),
+            )
+            .normalized(),
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Ray {
+    pub origin: Vector3<f32>,
+    pub direction: Vector3<f32>,
+}
+
+impl Ray {
+    pub fn new(origin: Vector3<f32>, direction: Vector3<f32>) -> Self {
+        Self {
+            origin,
+            direction: direction.normalized(),
+        }
+    }
+
+    pub fn at(&self, t: f32) -> Vector3<f32> {
+        &self.origin + &self.direction * t
+    }
+
+    pub fn transform(&self, m: &Matrix4<f32>) -> Self {
+        Self::new(
+            transform_point(m, &self.origin),
+            transform_vector(m, &self.direction),
+        )
+    }
+
+    pub fn intersect_plane(&self, plane: &Plane) -> Option<f32> {
+        let denom = plane.normal.dot(&self.direction);
+        if denom == 0.0 {
+            return None;
+        }
+        let t = -plane.signed_distance(&self.origin) / denom;
+        (t >= 0.0).then_some(t)
+    }
+
+    // the distance to the first hit, 0 when the origin is inside the box
+    pub fn intersect_aabb(&self, aabb: &Aabb) -> Option<f32> {
+        let mut t_min = f32::NEG_INFINITY;
+        let mut t_max = f32::INFINITY;
+
+        for i in 0..3 {
+            let inv = 1.0 / self.direction[i];
+            let a = (aabb.min[i] - self.origin[i]) * inv;
+            let b = (aabb.max[i] - self.origin[i]) * inv;
+            t_min = t_min.max(a.min(b));
+            t_max = t_max.min(a.max(b));
+        }
+
+        (t_max >= t_min.max(0.0)).then_some(t_min.max(0.0))
+    }
+
+    pub fn intersect_sphere(&self, sphere: &Sphere) -> Option<f32> {
+        let oc = &self.origin - &sphere.center;
+        let b = oc.dot(&self.direction);
+        let c = oc.dot(&oc) - sphere.radius * sphere.radius;
+        let discriminant = b * b - c;
+        if discriminant < 0.0 {
+            return None;
+        }
+        let sqrt = discriminant.sqrt();
+
+        [-b - sqrt, -b + sqrt].into_iter().find(|t| *t >= 0.0)
+    }
+
+    // Möller–Trumbore, both faces are hit
+    pub fn intersect_triangle(
+        &self,
+        a: &Vector3<f32>,
+        b: &Vector3<f32>,
+        c: &Vector3<f32>,
+    ) -> Option<f32> {
+        let e1 = b - a;
+        let e2 = c - a;
+        let p = self.direction.cross(&e2);
+        let det = e1.dot(&p);
+        if det.abs() < f32::EPSILON {
+            return None;
+        }
+        let inv = 1.0 / det;
+
+        let s = &self.origin - a;
+        let u = s.dot(&p) * inv;
+        if !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+        let q = s.cross(&e1);
+        let v = self.direction.dot(&q) * inv;
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+        let t = e2.dot(&q) * inv;
+        (t >= 0.0).then_some(t)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Frustum {
+    // left, right, bottom, top, near, far; all facing inwards
+    pub planes: [Plane; 6],
+}
+
+impl Frustum {
+    // expects a view projection matrix in wgpu clip space (z in 0..1), like `Camera::generate_transform_matrix`
+    pub fn from_matrix(m: &Matrix4<f32>) -> Self {
+        let row = |r: usize| [m[(0, r)], m[(1, r)], m[(2, r)], m[(3, r)]];
+        let plane = |a: [f32; 4], b: [f32; 4], s: f32| {
+            Plane::new(
+                vec3(a[0] + b[0] * s, a[1] + b[1] * s, a[2] + b[2] * s),
+                a[3] + b[3] * s,
+            )
+            .normalized()
+        };
+        let (r0, r1, r2, r3) = (row(0), row(1), row(2), row(3));
+
+        Self {
+            planes: [
+                plane(r3, r0, 1.0),
+                plane(r3, r0, -1.0),
+                plane(r3, r1, 1.0),
+                plane(r3, r1, -1.0),
+                plane(r2, r2, 0.0),
+                plane(r3, r2, -1.0),
+            ],
+        }
+    }
+
+    pub fn contains_point(&self, p: &Vector3<f32>) -> bool {
+        self.planes.iter().all(|x| x.signed_distance(p) >= 0.0)
+    }
+
+    // conservative, may report boxes near the frustum corners as visible
+    pub fn intersects_aabb(&self, aabb: &Aabb) -> bool {
+        self.planes.iter().all(|plane| {
+            let positive = vec3(
+                if *plane.normal.x() >= 0.0 {
+                    *aabb.max.x()
+                } else {
+                    *aabb.min.x()
+                },
+                if *plane.normal.y() >= 0.0 {
+                    *aabb.max.y()
+                } else {
+                    *aabb.min.y()
+                },
+                if *plane.normal.z() >= 0.0 {
+                    *aabb.max.z()
+                } else {
+                    *aabb.min.z()
+                },
+            );
+            plane.signed_distance(&positive) >= 0.0
+        })
+    }
+
+    pub fn intersects_sphere(&self, sphere: &Sphere) -> bool {
+        self.planes
+            .iter()
+            .all(|x| x.signed_distance(&sphere.center) >= -sphere.radius)
+    }
+}
+
+#[test]
+fn test_intersections() {
+    use super::transformable_matrices::{DepthMode, Prespective, TransformMatrix};
+
+    let unit = Aabb::new(vec3(-1.0, -1.0, -1.0), vec3(1.0, 1.0, 1.0));
+    let ray = Ray::new(vec3(0.0, 0.0, 5.0), vec3(0.0, 0.0, -2.0));
+
+    assert_eq!(ray.intersect_aabb(&unit), Some(4.0));
+    assert_eq!(
+        ray.intersect_sphere(&Sphere::new(Vector3::zeros(), 2.0)),
+        Some(3.0)
+    );
+    assert_eq!(
+        ray.intersect_triangle(
+            &vec3(-1.0, -1.0, 0.0),
+            &vec3(1.0, -1.0, 0.0),
+            &vec3(0.0, 1.0, 0.0)
+        ),
+        Some(5.0)
+    );
+    assert_eq!(
+        Ray::new(vec3(0.0, 3.0, 5.0), vec3(0.0, 0.0, -1.0)).intersect_aabb(&unit),
+        None
+    );
+
+    let moved = unit.transform(&Matrix4::from([
+        [2.0, 0.0, 0.0, 0.0],
+        [0.0, 1.0, 0.0, 0.0],
+        [0.0, 0.0, 1.0, 0.0],
+        [10.0, 0.0, 0.0, 1.0],
+    ]));
+    assert_eq!(moved.min.clone().into_arrays(), [[8.0, -1.0, -1.0]]);
+    assert_eq!(unit.merge(&moved).max.into_arrays(), [[12.0, 1.0, 1.0]]);
+
+    let view = Matrix4::look_at_rh(&vec3(0.0, 0.0, 0.0), &vec3(0.0, 0.0, -1.0), &Vector3::up());
+    let proj = Prespective {
+        fovy_rad: std::f32::consts::FRAC_PI_2,
+        aspect: 1.0,
+        near: 0.1,
+        far: 100.0,
+    };
+    for depth in [DepthMode::Standard, DepthMode::Reversed] {
+        let frustum = Frustum::from_matrix(&(proj.wgpu_matrix(depth) * &view));
+
+        assert!(frustum.contains_point(&vec3(0.0, 0.0, -10.0)));
+        assert!(!frustum.contains_point(&vec3(0.0, 0.0, 10.0)));
+        assert!(!frustum.contains_point(&vec3(0.0, 0.0, -200.0)));
+        assert!(frustum.intersects_aabb(&unit.transform(&Matrix4::from([
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, -5.0, 1.0],
+        ]))));
+        assert!(!frustum.intersects_aabb(&unit.transform(&Matrix4::from([
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 5.0, 1.0],
+        ]))));
+        assert!(frustum.intersects_sphere(&Sphere::new(vec3(12.0, 0.0, -10.0), 2.5)));
+        assert!(!frustum.intersects_sphere(&Sphere::new(vec3(13.0, 0.0, -10.0), 2.0)));
+    }
+}
@@ -0,0 +1,226 @@
+// every type here stores its matrices as columns, like `Matrix` (the outer array is a column),
+// so the conversions copy the columns in order and never transpose
+
+#[cfg(feature = "mint")]
+mod mint_impls {
+    use crate::math::matrices::{Matrix3, Matrix4, Vector3, Vector4};
+
+    impl<T> From<Matrix4<T>> for mint::ColumnMatrix4<T> {
+        fn from(value: Matrix4<T>) -> Self {
+            value.into_arrays().into()
+        }
+    }
+
+    impl<T> From<mint::ColumnMatrix4<T>> for Matrix4<T> {
+        fn from(value: mint::ColumnMatrix4<T>) -> Self {
+            <[[T; 4]; 4]>::from(value).into()
+        }
+    }
+
+    impl<T> From<Matrix3<T>> for mint::ColumnMatrix3<T> {
+        fn from(value: Matrix3<T>) -> Self {
+            value.into_arrays().into()
+        }
+    }
+
+    impl<T> From<mint::ColumnMatrix3<T>> for Matrix3<T> {
+        fn from(value: mint::ColumnMatrix3<T>) -> Self {
+            <[[T; 3]; 3]>::from(value).into()
+        }
+    }
+
+    impl<T> From<Vector4<T>> for mint::Vector4<T> {
+        fn from(value: Vector4<T>) -> Self {
+            let [column] = value.into_arrays();
+            column.into()
+        }
+    }
+
+    impl<T> From<mint::Vector4<T>> for Vector4<T> {
+        fn from(value: mint::Vector4<T>) -> Self {
+            [<[T; 4]>::from(value)].into()
+        }
+    }
+
+    impl<T> From<Vector3<T>> for mint::Vector3<T> {
+        fn from(value: Vector3<T>) -> Self {
+            let [column] = value.into_arrays();
+            column.into()
+        }
+    }
+
+    impl<T> From<mint::Vector3<T>> for Vector3<T> {
+        fn from(value: mint::Vector3<T>) -> Self {
+            [<[T; 3]>::from(value)].into()
+        }
+    }
+
+    #[test]
+    fn test_mint_layout() {
+        let m = Matrix4::from([
+            [1, 2, 3, 4],
+            [5, 6, 7, 8],
+            [9, 10, 11, 12],
+            [13, 14, 15, 16],
+        ]);
+        let mint = mint::ColumnMatrix4::from(m);
+        // the first array is the first column
+        assert_eq!(mint.x, mint::Vector4::from([1, 2, 3, 4]));
+        assert_eq!(mint.w, mint::Vector4::from([13, 14, 15, 16]));
+        let back = Matrix4::from(mint);
+        assert_eq!(back[(3, 1)], 14);
+
+        let v = mint::Vector3::from(Vector3::from([[1, 2, 3]]));
+        assert_eq!((v.x, v.y, v.z), (1, 2, 3));
+        assert_eq!(Vector3::from(v).into_arrays(), [[1, 2, 3]]);
+    }
+}
+
+#[cfg(feature = "glam")]
+mod glam_impls {
+    use crate::math::matrices::{Matrix3, Matrix4, Vector3, Vector4};
+
+    macro_rules! impl_glam {
+        ($t:ty, $mat4:ty, $mat3:ty, $vec4:ty, $vec3:ty) => {
+            impl From<Matrix4<$t>> for $mat4 {
+                fn from(value: Matrix4<$t>) -> Self {
+                    <$mat4>::from_cols_array_2d(value.as_arrays())
+                }
+            }
+
+            impl From<$mat4> for Matrix4<$t> {
+                fn from(value: $mat4) -> Self {
+                    value.to_cols_array_2d().into()
+                }
+            }
+
+            impl From<Matrix3<$t>> for $mat3 {
+                fn from(value: Matrix3<$t>) -> Self {
+                    <$mat3>::from_cols_array_2d(value.as_arrays())
+                }
+            }
+
+            impl From<$mat3> for Matrix3<$t> {
+                fn from(value: $mat3) -> Self {
+                    value.to_cols_array_2d().into()
+                }
+            }
+
+            impl From<Vector4<$t>> for $vec4 {
+                fn from(value: Vector4<$t>) -> Self {
+                    <$vec4>::from_array(value.as_arrays()[0])
+                }
+            }
+
+            impl From<$vec4> for Vector4<$t> {
+                fn from(value: $vec4) -> Self {
+                    [value.to_array()].into()
+                }
+            }
+
+            impl From<Vector3<$t>> for $vec3 {
+                fn from(value: Vector3<$t>) -> Self {
+                    <$vec3>::from_array(value.as_arrays()[0])
+                }
+            }
+
+            impl From<$vec3> for Vector3<$t> {
+                fn from(value: $vec3) -> Self {
+                    [value.to_array()].into()
+                }
+            }
+        };
+    }
+
+    impl_glam!(f32, glam::Mat4, glam::Mat3, glam::Vec4, glam::Vec3);
+    impl_glam!(f64, glam::DMat4, glam::DMat3, glam::DVec4, glam::DVec3);
+
+    #[test]
+    fn test_glam_layout() {
+        let m = Matrix4::<f32>::from([
+            [1.0, 2.0, 3.0, 4.0],
+            [5.0, 6.0, 7.0, 8.0],
+            [9.0, 10.0, 11.0, 12.0],
+            [13.0, 14.0, 15.0, 16.0],
+        ]);
+        let g = glam::Mat4::from(m);
+        // the first array is the first column, `Matrix[(col, row)]` is `Mat4::col(col)[row]`
+        assert_eq!(g.col(0), glam::Vec4::new(1.0, 2.0, 3.0, 4.0));
+        assert_eq!(g.row(0), glam::Vec4::new(1.0, 5.0, 9.0, 13.0));
+        assert_eq!(Matrix4::from(g)[(3, 1)], 14.0);
+
+        // the translation is in the last column for both
+        let mut t = Matrix4::<f32>::identity();
+        t[(3, 0)] = 1.0;
+        t[(3, 1)] = 2.0;
+        t[(3, 2)] = 3.0;
+        assert_eq!(
+            glam::Mat4::from(t),
+            glam::Mat4::from_translation(glam::Vec3::new(1.0, 2.0, 3.0))
+        );
+
+        let v = glam::DVec3::from(Vector3::<f64>::from([[1.0, 2.0, 3.0]]));
+        assert_eq!(v, glam::DVec3::new(1.0, 2.0, 3.0));
+        assert_eq!(Vector3::from(v).into_arrays(), [[1.0, 2.0, 3.0]]);
+    }
+}
+
+#[cfg(feature = "nalgebra")]
+mod nalgebra_impls {
+    use std::array;
+
+    use nalgebra::Scalar;
+
+    use crate::math::matrices::Matrix;
+
+    // nalgebra indexes `(row, col)` while `Matrix` indexes `(col, row)`
+    impl<T: Scalar, const N: usize, const M: usize> From<Matrix<T, N, M>>
+        for nalgebra::SMatrix<T, N, M>
+    {
+        fn from(value: Matrix<T, N, M>) -> Self {
+            Self::from_fn(|r, c| value[(c, r)].clone())
+        }
+    }
+
+    impl<T: Scalar, const N: usize, const M: usize> From<nalgebra::SMatrix<T, N, M>>
+        for Matrix<T, N, M>
+    {
+        fn from(value: nalgebra::SMatrix<T, N, M>) -> Self {
+            array::from_fn(|c| array::from_fn(|r| value[(r, c)].clone())).into()
+        }
+    }
+
+    #[test]
+    fn test_nalgebra_layout() {
+        use crate::math::matrices::{Matrix4, Vector3};
+
+        let m = Matrix4::<f32>::from([
+            [1.0, 2.0, 3.0, 4.0],
+            [5.0, 6.0, 7.0, 8.0],
+            [9.0, 10.0, 11.0, 12.0],
+            [13.0, 14.0, 15.0, 16.0],
+        ]);
+        let n = nalgebra::Matrix4::from(m);
+        // the first array is the first column, `Matrix[(col, row)]` is `na[(row, col)]`
+        assert_eq!(
+            n.column(0).into_owned(),
+            nalgebra::Vector4::new(1.0, 2.0, 3.0, 4.0)
+        );
+        assert_eq!(n[(1, 3)], 14.0);
+        assert_eq!(Matrix4::from(n)[(3, 1)], 14.0);
+
+        // the translation is in the last column for both
+        let mut t = Matrix4::<f32>::identity();
+        t[(3, 0)] = 1.0;
+        t[(3, 1)] = 2.0;
+        t[(3, 2)] = 3.0;
+        assert_eq!(
+            nalgebra::Matrix4::from(t),
+            nalgebra::Matrix4::new_translation(&nalgebra::Vector3::new(1.0, 2.0, 3.0))
+        );
+
+        let v = nalgebra::Vector3::from(Vector3::<f64>::from([[1.0, 2.0, 3.0]]));
+        assert_eq!(v, nalgebra::Vector3::new(1.0, 2.0, 3.0));
+        assert_eq!(Vector3::from(v).into_arrays(), [[1.0, 2.0, 3.0]]);
+    }
+}
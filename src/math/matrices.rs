@@ -5,6 +5,8 @@ use std::{
 
 use num_traits::{One, Zero};
 
+// `N` rows and `M` columns, stored as `M` columns of `N` values
+// every position in this crate is `(col, row)`: `Index`, `iter`, `map` and `from_fn`
 #[derive(Debug)]
 pub struct Matrix<T, const N: usize, const M: usize>([[T; N]; M]);
 
@@ -36,26 +38,26 @@ impl<T: Neg<Output = T> + Clone, const N: usize, const M: usize> Neg for Matrix<
 //     }
 // }
 
+// printed row by row
 impl<const N: usize, const M: usize, T: Display> Display for Matrix<T, N, M> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "[")?;
 
-        for (pos, x) in self.iter() {
-            if pos.0 == 0 {
-                if pos.1 != 0 {
-                    write!(f, " ")?;
-                }
-                write!(f, "[")?
+        for row in 0..N {
+            if row != 0 {
+                write!(f, " ")?;
             }
-            write!(f, "{}", x)?;
-            if pos.0 == N - 1 {
-                if pos.1 == M - 1 {
-                    write!(f, "]")?;
-                } else {
-                    writeln!(f, "],")?;
+            write!(f, "[")?;
+            for col in 0..M {
+                if col != 0 {
+                    write!(f, ",")?;
                 }
+                write!(f, "{}", self[(col, row)])?;
+            }
+            if row == N - 1 {
+                write!(f, "]")?;
             } else {
-                write!(f, ",")?;
+                writeln!(f, "],")?;
             }
         }
 
@@ -144,21 +146,60 @@ impl<const N: usize, const M: usize, T> Matrix<T, N, M> {
         self.0.iter().map(|x| Matrix::from([x.clone()]))
     }
 
-    // pub fn sub(&self, i: usize) -> Matrix<T, N, 1>
-    // where
-    //     T: Clone,
-    // {
-    //     if i < M {
-    //         Matrix(self.0.iter().skip(i * N).take(N).cloned().collect())
-    //     } else {
-    //         panic!("access bounds out of range");
-    //     }
-    // }
-    // pub fn set_sub(&mut self, i: usize, m: &Matrix<T, N, 1>) {
-    //     for x in 0..N {
-    //         self[(x, i)] = m[(x, 0)];
-    //     }
-    // }
+    pub fn from_fn(mut f: impl FnMut((usize, usize)) -> T) -> Self {
+        Self(std::array::from_fn(|col| {
+            std::array::from_fn(|row| f((col, row)))
+        }))
+    }
+    pub fn row(&self, row: usize) -> Matrix<T, M, 1>
+    where
+        T: Clone,
+    {
+        Matrix([std::array::from_fn(|col| self[(col, row)].clone())])
+    }
+    pub fn col(&self, col: usize) -> Matrix<T, N, 1>
+    where
+        T: Clone,
+    {
+        Matrix([self.0[col].clone()])
+    }
+    pub fn set_row(&mut self, row: usize, values: &Matrix<T, M, 1>)
+    where
+        T: Clone,
+    {
+        for col in 0..M {
+            self[(col, row)] = values[col].clone();
+        }
+    }
+    pub fn set_col(&mut self, col: usize, values: &Matrix<T, N, 1>)
+    where
+        T: Clone,
+    {
+        self.0[col] = values.0[0].clone();
+    }
+    // the `R` rows and `C` columns starting at row `r0` and column `c0`
+    pub fn submatrix<const R: usize, const C: usize>(&self, r0: usize, c0: usize) -> Matrix<T, R, C>
+    where
+        T: Clone,
+    {
+        assert!(r0 + R <= N && c0 + C <= M, "the submatrix is out of bounds");
+        Matrix::from_fn(|(col, row)| self[(c0 + col, r0 + row)].clone())
+    }
+    pub fn with_block<const R: usize, const C: usize>(
+        mut self,
+        r0: usize,
+        c0: usize,
+        block: &Matrix<T, R, C>,
+    ) -> Self
+    where
+        T: Clone,
+    {
+        assert!(r0 + R <= N && c0 + C <= M, "the block is out of bounds");
+        for ((col, row), x) in block.iter() {
+            self[(c0 + col, r0 + row)] = x.clone();
+        }
+        self
+    }
     pub fn add_matrix(&self, rhs: &Self) -> Self
     where
         T: Add<T, Output = T> + Clone,
@@ -210,6 +251,16 @@ impl<const N: usize, const M: usize, T> Matrix<T, N, M> {
     {
         Self::generate(|| T::one())
     }
+    pub fn diagonal(values: &Matrix<T, N, 1>) -> Self
+    where
+        T: Zero + Clone,
+    {
+        let mut ans = Self::zeros();
+        for i in 0..N.min(M) {
+            ans[(i, i)] = values[i].clone();
+        }
+        ans
+    }
     pub fn identity() -> Self
     where
         T: Zero + One,
@@ -225,6 +276,9 @@ impl<const N: usize, const M: usize, T> Matrix<T, N, M> {
     pub fn into_arrays(self) -> [[T; N]; M] {
         self.into()
     }
+    pub fn as_arrays(&self) -> &[[T; N]; M] {
+        &self.0
+    }
 }
 
 pub trait IntoMatrix<T,const N:usize,const M :usize> {
@@ -282,22 +336,42 @@ fn test_into() {
     println!("{:?}", m.into_arrays());
 }
 
-impl<T: Clone, const N: usize, const M: usize> From<&'_ Matrix<T, N, M>> for [[T; N]; M]
-where
-    T: Debug + Clone,
-{
+#[test]
+fn test_rows_and_columns() {
+    // 2 rows and 3 columns, written column by column
+    let m = Matrix::from([[1, 4], [2, 5], [3, 6]]);
+    assert_eq!(m[(2, 0)], 3);
+    assert_eq!(m.row(1).into_arrays(), [[4, 5, 6]]);
+    assert_eq!(m.col(2).into_arrays(), [[3, 6]]);
+    assert_eq!(<[[i32; 2]; 3]>::from(&m), m.clone().into_arrays());
+    assert_eq!(m.to_string(), "[[1,2,3],\n [4,5,6]]");
+
+    let f = Matrix::<i32, 2, 3>::from_fn(|(col, row)| (row * 3 + col + 1) as i32);
+    assert_eq!(f.as_arrays(), m.as_arrays());
+
+    let mut n = m.clone();
+    n.set_row(0, &Matrix::from([[7, 8, 9]]));
+    n.set_col(1, &Matrix::from([[0, 0]]));
+    assert_eq!(n.into_arrays(), [[7, 4], [0, 0], [9, 6]]);
+
+    let big = Matrix::<i32, 4, 4>::from_fn(|(col, row)| (row * 4 + col) as i32);
+    let sub = big.submatrix::<2, 3>(1, 1);
+    assert_eq!(sub.row(0).into_arrays(), [[5, 6, 7]]);
+    assert_eq!(sub.row(1).into_arrays(), [[9, 10, 11]]);
+
+    let blocked = Matrix::<i32, 4, 4>::zeros().with_block(2, 1, &m);
+    assert_eq!(blocked.row(2).into_arrays(), [[0, 1, 2, 3]]);
+    assert_eq!(blocked.row(3).into_arrays(), [[0, 4, 5, 6]]);
+    assert_eq!(blocked.submatrix::<2, 3>(2, 1).as_arrays(), m.as_arrays());
+
+    let d = Matrix::<i32, 3, 3>::diagonal(&Matrix::from([[1, 2, 3]]));
+    assert_eq!(d.into_arrays(), [[1, 0, 0], [0, 2, 0], [0, 0, 3]]);
+}
+
+// the same column layout as `From<Matrix>`
+impl<T: Clone, const N: usize, const M: usize> From<&'_ Matrix<T, N, M>> for [[T; N]; M] {
     fn from(val: &Matrix<T, N, M>) -> Self {
-        (0..M)
-            .map(|m| {
-                (0..N)
-                    .map(|n| val[(n, m)].clone())
-                    .collect::<Vec<_>>()
-                    .try_into()
-                    .unwrap()
-            })
-            .collect::<Vec<_>>()
-            .try_into()
-            .unwrap()
+        val.0.clone()
     }
 }
 
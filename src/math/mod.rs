@@ -1,3 +1,11 @@
+pub mod curves;
+pub mod geometry;
+mod interop;
 pub mod matrices;
+pub mod quaternions;
+#[cfg(feature = "serde")]
+mod serialization;
+pub mod simd;
+pub mod square_matrices;
 pub mod transformable_matrices;
 pub mod vectors;
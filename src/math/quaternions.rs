@@ -0,0 +1,417 @@
+use std::ops::{AddAssign, Mul, Neg};
+
+use num_traits::{cast, Float};
+
+use super::{
+    matrices::{Matrix3, Matrix4, Vector3, Vector4},
+    transformable_matrices::EulerOrder,
+    vectors::{Vector3D, Vector4D},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(
+        from = "[T; 4]",
+        into = "[T; 4]",
+        bound(serialize = "T: serde::Serialize + Clone")
+    )
+)]
+pub struct Quaternion<T> {
+    x: T,
+    y: T,
+    z: T,
+    w: T,
+}
+
+impl<T> Quaternion<T> {
+    pub fn new(x: T, y: T, z: T, w: T) -> Self {
+        Self { x, y, z, w }
+    }
+}
+
+impl<T: Float> Quaternion<T> {
+    pub fn identity() -> Self {
+        Self::new(T::zero(), T::zero(), T::zero(), T::one())
+    }
+
+    pub fn from_axis_angle(axis: &Vector3<T>, angle: T) -> Self {
+        let len = (*axis.x() * *axis.x() + *axis.y() * *axis.y() + *axis.z() * *axis.z()).sqrt();
+        if len.is_zero() {
+            return Self::identity();
+        }
+        let half = angle / Self::two();
+        let s = half.sin() / len;
+        Self::new(*axis.x() * s, *axis.y() * s, *axis.z() * s, half.cos())
+    }
+
+    pub fn rotate_x(angle: T) -> Self {
+        let half = angle / Self::two();
+        Self::new(half.sin(), T::zero(), T::zero(), half.cos())
+    }
+
+    pub fn rotate_y(angle: T) -> Self {
+        let half = angle / Self::two();
+        Self::new(T::zero(), half.sin(), T::zero(), half.cos())
+    }
+
+    pub fn rotate_z(angle: T) -> Self {
+        let half = angle / Self::two();
+        Self::new(T::zero(), T::zero(), half.sin(), half.cos())
+    }
+
+    pub fn rotate_axis(axis: usize, angle: T) -> Self {
+        match axis {
+            0 => Self::rotate_x(angle),
+            1 => Self::rotate_y(angle),
+            _ => Self::rotate_z(angle),
+        }
+    }
+
+    pub fn from_euler(euler: &Vector3<T>) -> Self {
+        Self::from_euler_ordered(euler, EulerOrder::default())
+    }
+
+    pub fn from_euler_ordered(euler: &Vector3<T>, order: EulerOrder) -> Self {
+        let [i, j, k] = order.axes();
+        Self::rotate_axis(i, euler[i])
+            * Self::rotate_axis(j, euler[j])
+            * Self::rotate_axis(k, euler[k])
+    }
+
+    pub fn to_euler(&self, order: EulerOrder) -> Vector3<T>
+    where
+        T: Default + AddAssign,
+    {
+        Matrix3::from(&self.normalized()).to_euler(order)
+    }
+
+    pub fn dot(&self, other: &Self) -> T {
+        self.x * other.x + self.y * other.y + self.z * other.z + self.w * other.w
+    }
+
+    pub fn length_squared(&self) -> T {
+        self.dot(self)
+    }
+
+    pub fn length(&self) -> T {
+        self.length_squared().sqrt()
+    }
+
+    pub fn normalized(&self) -> Self {
+        let len = self.length();
+        match len.is_zero() {
+            true => *self,
+            false => self.scale(T::one() / len),
+        }
+    }
+
+    pub fn conjugate(&self) -> Self {
+        Self::new(-self.x, -self.y, -self.z, self.w)
+    }
+
+    pub fn inverse(&self) -> Option<Self> {
+        let len = self.length_squared();
+        match len.is_zero() {
+            true => None,
+            false => Some(self.conjugate().scale(T::one() / len)),
+        }
+    }
+
+    pub fn nlerp(&self, other: &Self, t: T) -> Self {
+        let other = self.closest(other);
+        Self::new(
+            self.x + (other.x - self.x) * t,
+            self.y + (other.y - self.y) * t,
+            self.z + (other.z - self.z) * t,
+            self.w + (other.w - self.w) * t,
+        )
+        .normalized()
+    }
+
+    pub fn slerp(&self, other: &Self, t: T) -> Self {
+        let other = self.closest(other);
+        let cos = self.dot(&other).min(T::one());
+        let threshold: T = cast(0.9995_f32).expect("the threshold is needed");
+
+        if cos > threshold {
+            return self.nlerp(&other, t);
+        }
+
+        let theta = cos.acos();
+        let sin = theta.sin();
+        let a = ((T::one() - t) * theta).sin() / sin;
+        let b = (t * theta).sin() / sin;
+
+        Self::new(
+            self.x * a + other.x * b,
+            self.y * a + other.y * b,
+            self.z * a + other.z * b,
+            self.w * a + other.w * b,
+        )
+    }
+
+    pub fn rotate_vector(&self, v: &Vector3<T>) -> Vector3<T> {
+        let u = Vector3::from([[self.x, self.y, self.z]]);
+        let two = Self::two();
+        let uv = u.cross(v);
+        let uuv = u.cross(&uv);
+
+        Vector3::from([[
+            *v.x() + (*uv.x() * self.w + *uuv.x()) * two,
+            *v.y() + (*uv.y() * self.w + *uuv.y()) * two,
+            *v.z() + (*uv.z() * self.w + *uuv.z()) * two,
+        ]])
+    }
+
+    fn closest(&self, other: &Self) -> Self {
+        match self.dot(other) < T::zero() {
+            true => -*other,
+            false => *other,
+        }
+    }
+
+    fn scale(&self, s: T) -> Self {
+        Self::new(self.x * s, self.y * s, self.z * s, self.w * s)
+    }
+
+    fn two() -> T {
+        cast(2.0_f32).expect("the value 2 is needed")
+    }
+}
+
+impl<T> Vector4D<T> for Quaternion<T> {
+    fn x(&self) -> &T {
+        &self.x
+    }
+
+    fn y(&self) -> &T {
+        &self.y
+    }
+
+    fn z(&self) -> &T {
+        &self.z
+    }
+
+    fn w(&self) -> &T {
+        &self.w
+    }
+
+    fn x_mut(&mut self) -> &mut T {
+        &mut self.x
+    }
+
+    fn y_mut(&mut self) -> &mut T {
+        &mut self.y
+    }
+
+    fn z_mut(&mut self) -> &mut T {
+        &mut self.z
+    }
+
+    fn w_mut(&mut self) -> &mut T {
+        &mut self.w
+    }
+}
+
+impl<T: Float> Default for Quaternion<T> {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+impl<T: Float> Neg for Quaternion<T> {
+    type Output = Quaternion<T>;
+
+    fn neg(self) -> Self::Output {
+        Self::new(-self.x, -self.y, -self.z, -self.w)
+    }
+}
+
+impl<T: Float> Mul<&Quaternion<T>> for &Quaternion<T> {
+    type Output = Quaternion<T>;
+
+    fn mul(self, rhs: &Quaternion<T>) -> Self::Output {
+        Quaternion::new(
+            self.w * rhs.x + self.x * rhs.w + self.y * rhs.z - self.z * rhs.y,
+            self.w * rhs.y - self.x * rhs.z + self.y * rhs.w + self.z * rhs.x,
+            self.w * rhs.z + self.x * rhs.y - self.y * rhs.x + self.z * rhs.w,
+            self.w * rhs.w - self.x * rhs.x - self.y * rhs.y - self.z * rhs.z,
+        )
+    }
+}
+
+impl<T: Float> Mul<Quaternion<T>> for Quaternion<T> {
+    type Output = Quaternion<T>;
+
+    fn mul(self, rhs: Quaternion<T>) -> Self::Output {
+        &self * &rhs
+    }
+}
+
+impl<T: Float> Mul<&Vector3<T>> for &Quaternion<T> {
+    type Output = Vector3<T>;
+
+    fn mul(self, rhs: &Vector3<T>) -> Self::Output {
+        self.rotate_vector(rhs)
+    }
+}
+
+impl<T: Float> Mul<Vector3<T>> for Quaternion<T> {
+    type Output = Vector3<T>;
+
+    fn mul(self, rhs: Vector3<T>) -> Self::Output {
+        self.rotate_vector(&rhs)
+    }
+}
+
+impl<T> From<Vector4<T>> for Quaternion<T> {
+    fn from(value: Vector4<T>) -> Self {
+        let [[x, y, z, w]] = value.into_arrays();
+        Self::new(x, y, z, w)
+    }
+}
+
+impl<T> From<Quaternion<T>> for Vector4<T> {
+    fn from(value: Quaternion<T>) -> Self {
+        Vector4::from([[value.x, value.y, value.z, value.w]])
+    }
+}
+
+impl<T> From<[T; 4]> for Quaternion<T> {
+    fn from([x, y, z, w]: [T; 4]) -> Self {
+        Self::new(x, y, z, w)
+    }
+}
+
+impl<T> From<Quaternion<T>> for [T; 4] {
+    fn from(value: Quaternion<T>) -> Self {
+        [value.x, value.y, value.z, value.w]
+    }
+}
+
+// expects a pure rotation matrix
+impl<T: Float> From<&'_ Matrix3<T>> for Quaternion<T> {
+    fn from(m: &'_ Matrix3<T>) -> Self {
+        let r = |row: usize, col: usize| m[(col, row)];
+        let one = T::one();
+        let two = Self::two();
+        let quarter: T = cast(0.25_f32).expect("the value 0.25 is needed");
+        let trace = r(0, 0) + r(1, 1) + r(2, 2);
+
+        let q = if trace > T::zero() {
+            let s = (trace + one).sqrt() * two;
+            Self::new(
+                (r(2, 1) - r(1, 2)) / s,
+                (r(0, 2) - r(2, 0)) / s,
+                (r(1, 0) - r(0, 1)) / s,
+                quarter * s,
+            )
+        } else if r(0, 0) > r(1, 1) && r(0, 0) > r(2, 2) {
+            let s = (one + r(0, 0) - r(1, 1) - r(2, 2)).sqrt() * two;
+            Self::new(
+                quarter * s,
+                (r(0, 1) + r(1, 0)) / s,
+                (r(0, 2) + r(2, 0)) / s,
+                (r(2, 1) - r(1, 2)) / s,
+            )
+        } else if r(1, 1) > r(2, 2) {
+            let s = (one + r(1, 1) - r(0, 0) - r(2, 2)).sqrt() * two;
+            Self::new(
+                (r(0, 1) + r(1, 0)) / s,
+                quarter * s,
+                (r(1, 2) + r(2, 1)) / s,
+                (r(0, 2) - r(2, 0)) / s,
+            )
+        } else {
+            let s = (one + r(2, 2) - r(0, 0) - r(1, 1)).sqrt() * two;
+            Self::new(
+                (r(0, 2) + r(2, 0)) / s,
+                (r(1, 2) + r(2, 1)) / s,
+                quarter * s,
+                (r(1, 0) - r(0, 1)) / s,
+            )
+        };
+        q.normalized()
+    }
+}
+
+impl<T: Float> From<Matrix3<T>> for Quaternion<T> {
+    fn from(value: Matrix3<T>) -> Self {
+        (&value).into()
+    }
+}
+
+impl<T: Float> From<&'_ Quaternion<T>> for Matrix3<T> {
+    fn from(q: &'_ Quaternion<T>) -> Self {
+        let one = T::one();
+        let two = Quaternion::<T>::two();
+        let (x, y, z, w) = (q.x, q.y, q.z, q.w);
+
+        Matrix3::from([
+            [
+                one - two * (y * y + z * z),
+                two * (x * y + w * z),
+                two * (x * z - w * y),
+            ],
+            [
+                two * (x * y - w * z),
+                one - two * (x * x + z * z),
+                two * (y * z + w * x),
+            ],
+            [
+                two * (x * z + w * y),
+                two * (y * z - w * x),
+                one - two * (x * x + y * y),
+            ],
+        ])
+    }
+}
+
+impl<T: Float> From<Quaternion<T>> for Matrix3<T> {
+    fn from(value: Quaternion<T>) -> Self {
+        (&value).into()
+    }
+}
+
+impl<T: Float> From<&'_ Quaternion<T>> for Matrix4<T> {
+    fn from(q: &'_ Quaternion<T>) -> Self {
+        let [a, b, c] = Matrix3::from(q).into_arrays();
+        let zero = T::zero();
+
+        Matrix4::from([
+            [a[0], a[1], a[2], zero],
+            [b[0], b[1], b[2], zero],
+            [c[0], c[1], c[2], zero],
+            [zero, zero, zero, T::one()],
+        ])
+    }
+}
+
+impl<T: Float> From<Quaternion<T>> for Matrix4<T> {
+    fn from(value: Quaternion<T>) -> Self {
+        (&value).into()
+    }
+}
+
+#[test]
+fn test_quaternion_rotation() {
+    use std::f32::consts::FRAC_PI_2;
+
+    let q = Quaternion::from_axis_angle(&Vector3::from([[0.0, 0.0, 1.0]]), FRAC_PI_2);
+    let v = q.rotate_vector(&Vector3::from([[1.0, 0.0, 0.0]]));
+    let m = Matrix3::from(&q) * Vector3::from([[1.0, 0.0, 0.0]]);
+
+    for i in 0..3 {
+        assert!((v[i] - [0.0, 1.0, 0.0][i]).abs() < 1e-6);
+        assert!((m[i] - v[i]).abs() < 1e-6);
+    }
+
+    let back = q * q.inverse().unwrap();
+    assert!((back.dot(&Quaternion::identity()) - 1.0).abs() < 1e-6);
+
+    let half = Quaternion::identity().slerp(&q, 0.5);
+    let expected = Quaternion::rotate_z(FRAC_PI_2 / 2.0);
+    assert!((half.dot(&expected) - 1.0).abs() < 1e-6);
+}
@@ -0,0 +1,151 @@
+use std::{fmt, marker::PhantomData};
+
+use serde::{
+    de::{self, SeqAccess, Visitor},
+    ser::SerializeSeq,
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+use super::matrices::Matrix;
+
+// vectors (a single column) are written as `[x, y, z]`, every other matrix as a list of columns
+// `[[..], [..]]`, the same layout `Matrix::from` takes
+impl<T: Serialize, const N: usize, const M: usize> Serialize for Matrix<T, N, M> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if M == 1 {
+            return Column(&self.as_arrays()[0]).serialize(serializer);
+        }
+        let mut seq = serializer.serialize_seq(Some(M))?;
+        for column in self.as_arrays() {
+            seq.serialize_element(&Column(column))?;
+        }
+        seq.end()
+    }
+}
+
+struct Column<'a, T, const N: usize>(&'a [T; N]);
+
+impl<T: Serialize, const N: usize> Serialize for Column<'_, T, N> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(N))?;
+        for x in self.0 {
+            seq.serialize_element(x)?;
+        }
+        seq.end()
+    }
+}
+
+impl<'de, T: Deserialize<'de>, const N: usize, const M: usize> Deserialize<'de>
+    for Matrix<T, N, M>
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if M == 1 {
+            let column = deserializer.deserialize_seq(ArrayVisitor::<T, N>(PhantomData))?;
+            let columns: [[T; N]; M] = vec![column].try_into().unwrap_or_else(|_| unreachable!());
+            return Ok(columns.into());
+        }
+        let columns =
+            deserializer.deserialize_seq(ArrayVisitor::<ColumnArray<T, N>, M>(PhantomData))?;
+        Ok(columns.map(|x| x.0).into())
+    }
+}
+
+struct ColumnArray<T, const N: usize>([T; N]);
+
+impl<'de, T: Deserialize<'de>, const N: usize> Deserialize<'de> for ColumnArray<T, N> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer
+            .deserialize_seq(ArrayVisitor::<T, N>(PhantomData))
+            .map(ColumnArray)
+    }
+}
+
+struct ArrayVisitor<T, const N: usize>(PhantomData<T>);
+
+impl<'de, T: Deserialize<'de>, const N: usize> Visitor<'de> for ArrayVisitor<T, N> {
+    type Value = [T; N];
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "an array of length {}", N)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut values = Vec::with_capacity(N);
+        while let Some(x) = seq.next_element()? {
+            if values.len() == N {
+                return Err(de::Error::invalid_length(N + 1, &self));
+            }
+            values.push(x);
+        }
+        let len = values.len();
+        values
+            .try_into()
+            .map_err(|_| de::Error::invalid_length(len, &self))
+    }
+}
+
+#[test]
+fn test_serde_round_trip() {
+    use super::{
+        matrices::{Matrix4, Vector3},
+        quaternions::Quaternion,
+        transformable_matrices::{DepthMode, Prespective},
+    };
+    use crate::{pipelines::transform::Transform, renderer::camera::Camera};
+
+    let v: Vector3<f32> = [[1.0, -2.5, 3.0]].into();
+    assert_eq!(serde_json::to_string(&v).unwrap(), "[1.0,-2.5,3.0]");
+    assert_eq!(ron::to_string(&v).unwrap(), "[1.0,-2.5,3.0]");
+
+    let m = Matrix4::<f32>::identity();
+    let json = serde_json::to_string(&m).unwrap();
+    assert_eq!(
+        json,
+        "[[1.0,0.0,0.0,0.0],[0.0,1.0,0.0,0.0],[0.0,0.0,1.0,0.0],[0.0,0.0,0.0,1.0]]"
+    );
+    let back: Matrix4<f32> = serde_json::from_str(&json).unwrap();
+    assert_eq!(back.as_arrays(), m.as_arrays());
+    let back: Matrix4<f32> = ron::from_str(&ron::to_string(&m).unwrap()).unwrap();
+    assert_eq!(back.as_arrays(), m.as_arrays());
+    assert!(serde_json::from_str::<Vector3<f32>>("[1.0,2.0]").is_err());
+    assert!(serde_json::from_str::<Vector3<f32>>("[1.0,2.0,3.0,4.0]").is_err());
+
+    let camera = Camera::new(
+        Transform::identity()
+            .with_position(v.clone())
+            .with_rotateion(Quaternion::from_euler(&[[0.1, 0.2, 0.3]].into()))
+            .with_scale([[1.0, 2.0, 3.0]].into()),
+        Prespective {
+            fovy_rad: 1.0,
+            aspect: 2.0,
+            near: 0.1,
+            far: 100.0,
+        }
+        .into(),
+    )
+    .with_depth(DepthMode::Reversed)
+    .with_world_position([[1e9, 0.5, -2.0]].into());
+
+    let check = |back: Camera| {
+        assert_eq!(
+            back.transform.position().as_arrays(),
+            camera.transform.position().as_arrays()
+        );
+        assert_eq!(
+            back.transform.scale().as_arrays(),
+            camera.transform.scale().as_arrays()
+        );
+        assert_eq!(back.transform.rotation(), camera.transform.rotation());
+        assert_eq!(back.depth, camera.depth);
+        assert_eq!(
+            back.world_position.unwrap().as_arrays(),
+            camera.world_position.as_ref().unwrap().as_arrays()
+        );
+        assert_eq!(
+            back.projection.wgpu_matrix(back.depth).as_arrays(),
+            camera.projection.wgpu_matrix(camera.depth).as_arrays()
+        );
+    };
+    check(serde_json::from_str(&serde_json::to_string(&camera).unwrap()).unwrap());
+    check(ron::from_str(&ron::to_string(&camera).unwrap()).unwrap());
+}
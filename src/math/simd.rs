@@ -0,0 +1,95 @@
+use super::matrices::{Matrix4, Vector4};
+
+// every output column is `sum(a_col[m] * b[k][m])` accumulated from zero in the same order as
+// `Matrix::mul_matrix`, and without fused multiply-add, so the results are bit-identical to it
+#[cfg(target_arch = "x86_64")]
+fn mul_columns<const K: usize>(a: &[[f32; 4]; 4], b: &[[f32; 4]; K]) -> [[f32; 4]; K] {
+    use std::arch::x86_64::{
+        _mm_add_ps, _mm_loadu_ps, _mm_mul_ps, _mm_set1_ps, _mm_setzero_ps, _mm_storeu_ps,
+    };
+
+    let mut ans = [[0.0; 4]; K];
+    unsafe {
+        let cols = a.map(|col| _mm_loadu_ps(col.as_ptr()));
+        for (out, b) in ans.iter_mut().zip(b) {
+            let mut acc = _mm_setzero_ps();
+            for (col, x) in cols.iter().zip(b) {
+                acc = _mm_add_ps(acc, _mm_mul_ps(*col, _mm_set1_ps(*x)));
+            }
+            _mm_storeu_ps(out.as_mut_ptr(), acc);
+        }
+    }
+    ans
+}
+
+#[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
+fn mul_columns<const K: usize>(a: &[[f32; 4]; 4], b: &[[f32; 4]; K]) -> [[f32; 4]; K] {
+    use std::arch::aarch64::{vaddq_f32, vdupq_n_f32, vld1q_f32, vmulq_f32, vst1q_f32};
+
+    let mut ans = [[0.0; 4]; K];
+    unsafe {
+        let cols = a.map(|col| vld1q_f32(col.as_ptr()));
+        for (out, b) in ans.iter_mut().zip(b) {
+            let mut acc = vdupq_n_f32(0.0);
+            for (col, x) in cols.iter().zip(b) {
+                acc = vaddq_f32(acc, vmulq_f32(*col, vdupq_n_f32(*x)));
+            }
+            vst1q_f32(out.as_mut_ptr(), acc);
+        }
+    }
+    ans
+}
+
+#[cfg(not(any(
+    target_arch = "x86_64",
+    all(target_arch = "aarch64", target_feature = "neon")
+)))]
+fn mul_columns<const K: usize>(a: &[[f32; 4]; 4], b: &[[f32; 4]; K]) -> [[f32; 4]; K] {
+    let mut ans = [[0.0; 4]; K];
+    for (out, b) in ans.iter_mut().zip(b) {
+        for (r, x) in out.iter_mut().enumerate() {
+            for (col, y) in a.iter().zip(b) {
+                *x += col[r] * y;
+            }
+        }
+    }
+    ans
+}
+
+impl Matrix4<f32> {
+    pub fn mul_matrix4(&self, rhs: &Matrix4<f32>) -> Matrix4<f32> {
+        mul_columns(self.as_arrays(), rhs.as_arrays()).into()
+    }
+
+    pub fn mul_vector4(&self, rhs: &Vector4<f32>) -> Vector4<f32> {
+        mul_columns(self.as_arrays(), rhs.as_arrays()).into()
+    }
+}
+
+#[test]
+fn test_simd_mul() {
+    let mut seed = 0x2545_f491_u32;
+    let mut next = || {
+        seed ^= seed << 13;
+        seed ^= seed >> 17;
+        seed ^= seed << 5;
+        (seed as f32 / u32::MAX as f32 - 0.5) * 200.0
+    };
+
+    for _ in 0..100 {
+        let mut a = Matrix4::<f32>::generate(&mut next);
+        let b = Matrix4::<f32>::generate(&mut next);
+        let v = Vector4::<f32>::generate(&mut next);
+        a[(1, 2)] = -0.0;
+        a[(3, 0)] = f32::MIN_POSITIVE / 3.0;
+
+        let fast = a.mul_matrix4(&b);
+        for (pos, x) in a.mul_matrix(&b).iter() {
+            assert_eq!(x.to_bits(), fast[pos].to_bits());
+        }
+        let fast = a.mul_vector4(&v);
+        for (pos, x) in a.mul_matrix(&v).iter() {
+            assert_eq!(x.to_bits(), fast[pos].to_bits());
+        }
+    }
+}
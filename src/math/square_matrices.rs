@@ -0,0 +1,354 @@
+use std::cmp::Ordering;
+
+use num_traits::Float;
+
+use super::matrices::Matrix;
+
+pub struct LuDecomposition<T, const N: usize> {
+    // row major, L below the diagonal (with an implicit unit diagonal) and U on and above it
+    lu: [[T; N]; N],
+    pivots: [usize; N],
+    sign: T,
+}
+
+impl<T: Float, const N: usize> LuDecomposition<T, N> {
+    pub fn determinant(&self) -> T {
+        (0..N).fold(self.sign, |acc, i| acc * self.lu[i][i])
+    }
+
+    pub fn solve(&self, b: &Matrix<T, N, 1>) -> Matrix<T, N, 1> {
+        let mut x = Matrix::<T, N, 1>::zeros();
+        for i in 0..N {
+            x[i] = b[self.pivots[i]];
+        }
+        for i in 0..N {
+            for k in 0..i {
+                x[i] = x[i] - self.lu[i][k] * x[k];
+            }
+        }
+        for i in (0..N).rev() {
+            for k in (i + 1)..N {
+                x[i] = x[i] - self.lu[i][k] * x[k];
+            }
+            x[i] = x[i] / self.lu[i][i];
+        }
+        x
+    }
+}
+
+impl<T: Float, const N: usize> Matrix<T, N, N> {
+    fn row_major(&self) -> [[T; N]; N] {
+        let mut ans = [[T::zero(); N]; N];
+        for (pos, x) in self.iter() {
+            ans[pos.1][pos.0] = *x;
+        }
+        ans
+    }
+
+    fn max_abs(&self) -> T {
+        self.iter().fold(T::zero(), |acc, (_, x)| acc.max(x.abs()))
+    }
+
+    // `None` for singular matrices and matrices with a NaN
+    pub fn lu(&self) -> Option<LuDecomposition<T, N>> {
+        if self.iter().any(|(_, x)| x.is_nan()) {
+            return None;
+        }
+        let mut lu = self.row_major();
+        let mut pivots = [0; N];
+        let mut sign = T::one();
+        let tolerance = T::epsilon() * self.max_abs();
+
+        for (i, p) in pivots.iter_mut().enumerate() {
+            *p = i;
+        }
+
+        for k in 0..N {
+            let p = (k..N)
+                .max_by(|a, b| {
+                    lu[*a][k]
+                        .abs()
+                        .partial_cmp(&lu[*b][k].abs())
+                        .unwrap_or(Ordering::Equal)
+                })
+                .unwrap();
+            if lu[p][k].abs() <= tolerance {
+                return None;
+            }
+            if p != k {
+                lu.swap(p, k);
+                pivots.swap(p, k);
+                sign = -sign;
+            }
+            for i in (k + 1)..N {
+                lu[i][k] = lu[i][k] / lu[k][k];
+                for j in (k + 1)..N {
+                    lu[i][j] = lu[i][j] - lu[i][k] * lu[k][j];
+                }
+            }
+        }
+
+        Some(LuDecomposition { lu, pivots, sign })
+    }
+
+    pub fn determinant(&self) -> T {
+        match N {
+            0 => T::one(),
+            1 => self[(0, 0)],
+            2 => self[(0, 0)] * self[(1, 1)] - self[(0, 1)] * self[(1, 0)],
+            3 => self.determinant3(),
+            4 => self.determinant4(),
+            _ => self.lu().map(|x| x.determinant()).unwrap_or_else(T::zero),
+        }
+    }
+
+    pub fn inverse(&self) -> Option<Self> {
+        match N {
+            3 => self.inverse3(),
+            4 => self.inverse4(),
+            _ => {
+                let lu = self.lu()?;
+                let mut ans = Self::zeros();
+                for c in 0..N {
+                    let mut e = Matrix::<T, N, 1>::zeros();
+                    e[c] = T::one();
+                    let col = lu.solve(&e);
+                    for r in 0..N {
+                        ans[(c, r)] = col[r];
+                    }
+                }
+                Some(ans)
+            }
+        }
+    }
+
+    pub fn solve(&self, b: &Matrix<T, N, 1>) -> Option<Matrix<T, N, 1>> {
+        self.lu().map(|lu| lu.solve(b))
+    }
+
+    // the determinant of the matrix without the given column and row, indexed like `self[(col, row)]`
+    pub fn minor(&self, (col, row): (usize, usize)) -> T {
+        let rows = self
+            .row_major()
+            .iter()
+            .enumerate()
+            .filter(|(r, _)| *r != row)
+            .map(|(_, x)| {
+                x.iter()
+                    .enumerate()
+                    .filter(|(c, _)| *c != col)
+                    .map(|(_, x)| *x)
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        determinant_of_rows(rows)
+    }
+
+    pub fn cofactor(&self, pos: (usize, usize)) -> T {
+        match (pos.0 + pos.1) % 2 {
+            0 => self.minor(pos),
+            _ => -self.minor(pos),
+        }
+    }
+
+    pub fn cofactor_matrix(&self) -> Self {
+        let mut ans = Self::zeros();
+        for (pos, x) in ans.iter_mut() {
+            *x = self.cofactor(pos);
+        }
+        ans
+    }
+
+    pub fn adjugate(&self) -> Self {
+        let mut ans = Self::zeros();
+        for (pos, x) in ans.iter_mut() {
+            *x = self.cofactor((pos.1, pos.0));
+        }
+        ans
+    }
+
+    fn determinant3(&self) -> T {
+        let a = |i: usize, j: usize| self[(i, j)];
+
+        a(0, 0) * (a(1, 1) * a(2, 2) - a(1, 2) * a(2, 1))
+            - a(0, 1) * (a(1, 0) * a(2, 2) - a(1, 2) * a(2, 0))
+            + a(0, 2) * (a(1, 0) * a(2, 1) - a(1, 1) * a(2, 0))
+    }
+
+    fn inverse3(&self) -> Option<Self> {
+        let a = |i: usize, j: usize| self[(i, j)];
+        let det = self.determinant3();
+        if det.is_nan() || det.abs() <= T::epsilon() * self.max_abs().powi(3) {
+            return None;
+        }
+        let inv = T::one() / det;
+
+        let mut ans = Self::zeros();
+        ans[(0, 0)] = (a(1, 1) * a(2, 2) - a(1, 2) * a(2, 1)) * inv;
+        ans[(0, 1)] = (a(0, 2) * a(2, 1) - a(0, 1) * a(2, 2)) * inv;
+        ans[(0, 2)] = (a(0, 1) * a(1, 2) - a(0, 2) * a(1, 1)) * inv;
+        ans[(1, 0)] = (a(1, 2) * a(2, 0) - a(1, 0) * a(2, 2)) * inv;
+        ans[(1, 1)] = (a(0, 0) * a(2, 2) - a(0, 2) * a(2, 0)) * inv;
+        ans[(1, 2)] = (a(0, 2) * a(1, 0) - a(0, 0) * a(1, 2)) * inv;
+        ans[(2, 0)] = (a(1, 0) * a(2, 1) - a(1, 1) * a(2, 0)) * inv;
+        ans[(2, 1)] = (a(0, 1) * a(2, 0) - a(0, 0) * a(2, 1)) * inv;
+        ans[(2, 2)] = (a(0, 0) * a(1, 1) - a(0, 1) * a(1, 0)) * inv;
+        Some(ans)
+    }
+
+    fn sub_determinants4(&self) -> ([T; 6], [T; 6]) {
+        let a = |i: usize, j: usize| self[(i, j)];
+        (
+            [
+                a(0, 0) * a(1, 1) - a(1, 0) * a(0, 1),
+                a(0, 0) * a(1, 2) - a(1, 0) * a(0, 2),
+                a(0, 0) * a(1, 3) - a(1, 0) * a(0, 3),
+                a(0, 1) * a(1, 2) - a(1, 1) * a(0, 2),
+                a(0, 1) * a(1, 3) - a(1, 1) * a(0, 3),
+                a(0, 2) * a(1, 3) - a(1, 2) * a(0, 3),
+            ],
+            [
+                a(2, 0) * a(3, 1) - a(3, 0) * a(2, 1),
+                a(2, 0) * a(3, 2) - a(3, 0) * a(2, 2),
+                a(2, 0) * a(3, 3) - a(3, 0) * a(2, 3),
+                a(2, 1) * a(3, 2) - a(3, 1) * a(2, 2),
+                a(2, 1) * a(3, 3) - a(3, 1) * a(2, 3),
+                a(2, 2) * a(3, 3) - a(3, 2) * a(2, 3),
+            ],
+        )
+    }
+
+    fn determinant4(&self) -> T {
+        let (s, c) = self.sub_determinants4();
+        s[0] * c[5] - s[1] * c[4] + s[2] * c[3] + s[3] * c[2] - s[4] * c[1] + s[5] * c[0]
+    }
+
+    fn inverse4(&self) -> Option<Self> {
+        let a = |i: usize, j: usize| self[(i, j)];
+        let (s, c) = self.sub_determinants4();
+        let det = s[0] * c[5] - s[1] * c[4] + s[2] * c[3] + s[3] * c[2] - s[4] * c[1] + s[5] * c[0];
+        if det.is_nan() || det.abs() <= T::epsilon() * self.max_abs().powi(4) {
+            return None;
+        }
+        let inv = T::one() / det;
+
+        let mut ans = Self::zeros();
+        ans[(0, 0)] = (a(1, 1) * c[5] - a(1, 2) * c[4] + a(1, 3) * c[3]) * inv;
+        ans[(0, 1)] = (-a(0, 1) * c[5] + a(0, 2) * c[4] - a(0, 3) * c[3]) * inv;
+        ans[(0, 2)] = (a(3, 1) * s[5] - a(3, 2) * s[4] + a(3, 3) * s[3]) * inv;
+        ans[(0, 3)] = (-a(2, 1) * s[5] + a(2, 2) * s[4] - a(2, 3) * s[3]) * inv;
+        ans[(1, 0)] = (-a(1, 0) * c[5] + a(1, 2) * c[2] - a(1, 3) * c[1]) * inv;
+        ans[(1, 1)] = (a(0, 0) * c[5] - a(0, 2) * c[2] + a(0, 3) * c[1]) * inv;
+        ans[(1, 2)] = (-a(3, 0) * s[5] + a(3, 2) * s[2] - a(3, 3) * s[1]) * inv;
+        ans[(1, 3)] = (a(2, 0) * s[5] - a(2, 2) * s[2] + a(2, 3) * s[1]) * inv;
+        ans[(2, 0)] = (a(1, 0) * c[4] - a(1, 1) * c[2] + a(1, 3) * c[0]) * inv;
+        ans[(2, 1)] = (-a(0, 0) * c[4] + a(0, 1) * c[2] - a(0, 3) * c[0]) * inv;
+        ans[(2, 2)] = (a(3, 0) * s[4] - a(3, 1) * s[2] + a(3, 3) * s[0]) * inv;
+        ans[(2, 3)] = (-a(2, 0) * s[4] + a(2, 1) * s[2] - a(2, 3) * s[0]) * inv;
+        ans[(3, 0)] = (-a(1, 0) * c[3] + a(1, 1) * c[1] - a(1, 2) * c[0]) * inv;
+        ans[(3, 1)] = (a(0, 0) * c[3] - a(0, 1) * c[1] + a(0, 2) * c[0]) * inv;
+        ans[(3, 2)] = (-a(3, 0) * s[3] + a(3, 1) * s[1] - a(3, 2) * s[0]) * inv;
+        ans[(3, 3)] = (a(2, 0) * s[3] - a(2, 1) * s[1] + a(2, 2) * s[0]) * inv;
+        Some(ans)
+    }
+}
+
+fn determinant_of_rows<T: Float>(mut rows: Vec<Vec<T>>) -> T {
+    let n = rows.len();
+    let mut det = T::one();
+
+    for k in 0..n {
+        let p = (k..n)
+            .max_by(|a, b| {
+                rows[*a][k]
+                    .abs()
+                    .partial_cmp(&rows[*b][k].abs())
+                    .unwrap_or(Ordering::Equal)
+            })
+            .unwrap();
+        if rows[p][k].is_zero() {
+            return T::zero();
+        }
+        if p != k {
+            rows.swap(p, k);
+            det = -det;
+        }
+        det = det * rows[k][k];
+        let pivot = rows[k].clone();
+        for row in rows.iter_mut().skip(k + 1) {
+            let f = row[k] / pivot[k];
+            for (x, p) in row.iter_mut().zip(&pivot).skip(k) {
+                *x = *x - f * *p;
+            }
+        }
+    }
+    det
+}
+
+#[test]
+fn test_inverse() {
+    use super::matrices::{Matrix3, Matrix4};
+
+    let m = Matrix4::from([
+        [2.0, 0.0, 1.0, 0.0],
+        [1.0, 3.0, 0.0, 0.0],
+        [0.0, 1.0, 4.0, 1.0],
+        [5.0, -2.0, 1.0, 1.0],
+    ]);
+    let generic = Matrix::<f64, 5, 5>::from([
+        [2.0, 0.0, 1.0, 0.0, 0.0],
+        [1.0, 3.0, 0.0, 0.0, 0.0],
+        [0.0, 1.0, 4.0, 1.0, 0.0],
+        [5.0, -2.0, 1.0, 1.0, 0.0],
+        [0.0, 0.0, 0.0, 0.0, 1.0],
+    ]);
+
+    let inv = m.inverse().unwrap();
+    let generic_inv = generic.inverse().unwrap();
+    for (pos, x) in (&m * &inv).iter() {
+        let expected = if pos.0 == pos.1 { 1.0 } else { 0.0 };
+        assert!((x - expected).abs() < 1e-12);
+        assert!((inv[pos] - generic_inv[pos]).abs() < 1e-12);
+    }
+
+    assert!((m.determinant() - generic.determinant()).abs() < 1e-12);
+    assert!((m.determinant() - m.lu().unwrap().determinant()).abs() < 1e-12);
+
+    let b = Matrix::from([[1.0, 2.0, 3.0, 4.0]]);
+    let x = m.solve(&b).unwrap();
+    for (pos, v) in (&m * &x).iter() {
+        assert!((v - b[pos]).abs() < 1e-12);
+    }
+
+    let singular = Matrix3::from([[1.0, 2.0, 3.0], [2.0, 4.0, 6.0], [0.0, 1.0, 1.0]]);
+    assert!(singular.inverse().is_none());
+    assert!(singular.solve(&Matrix::from([[1.0, 1.0, 1.0]])).is_none());
+    assert_eq!(singular.determinant(), 0.0);
+
+    let adjugate = m.adjugate();
+    for (pos, x) in (&inv * m.determinant()).iter() {
+        assert!((x - adjugate[pos]).abs() < 1e-12);
+    }
+}
+
+#[test]
+fn test_nan() {
+    use super::matrices::{Matrix3, Matrix4};
+
+    let mut m = Matrix4::<f64>::identity();
+    m[(1, 2)] = f64::NAN;
+    assert!(m.inverse().is_none());
+    assert!(m.determinant().is_nan());
+
+    let mut m = Matrix3::<f64>::identity();
+    m[(0, 0)] = f64::NAN;
+    assert!(m.inverse().is_none());
+
+    let mut generic = Matrix::<f64, 5, 5>::identity();
+    generic[(3, 1)] = f64::NAN;
+    assert!(generic.lu().is_none());
+    assert!(generic.inverse().is_none());
+    assert!(generic.solve(&Matrix::from([[1.0; 5]])).is_none());
+    assert!(generic.minor((0, 0)).is_nan());
+}
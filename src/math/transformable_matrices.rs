@@ -4,6 +4,7 @@ use num_traits::{cast, Float, One, Zero};
 
 use super::{
     matrices::{Matrix3, Matrix4, Vector3},
+    quaternions::Quaternion,
     vectors::{Vector, Vector3D},
 };
 
@@ -47,6 +48,26 @@ impl<T: Mul<Output = T> + Add<Output = T> + AddAssign<T> + Zero + Float> Transfo
         Self::look_to_lh(eye, &(center - eye), up)
     }
 }
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DepthMode {
+    // near maps to 0 and far to 1
+    #[default]
+    Standard,
+    // near maps to 1 and far to 0, which spreads the float precision evenly over the distance
+    Reversed,
+}
+
+impl DepthMode {
+    pub fn clear_value(&self) -> f32 {
+        match self {
+            DepthMode::Standard => 1.0,
+            DepthMode::Reversed => 0.0,
+        }
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Prespective<T> {
     pub fovy_rad: T,
     pub aspect: T,
@@ -87,27 +108,188 @@ impl<T: Zero + Float> From<Prespective<T>> for Matrix4<T> {
     }
 }
 
+impl<T: Float> Prespective<T> {
+    // builds the matrix directly in wgpu clip space (z in 0..1), `far` may be infinite
+    pub fn wgpu_matrix(&self, depth: DepthMode) -> Matrix4<T> {
+        assert!(self.near > T::zero());
+        assert!(self.near < self.far);
+        assert!(!self.aspect.is_zero());
+
+        let two: T = cast(2.0_f32).expect("the value 2 is needed");
+
+        let f = T::one() / (self.fovy_rad / two).tan();
+        let (near, far) = (self.near, self.far);
+        let (z, w) = match (depth, far.is_infinite()) {
+            (DepthMode::Standard, false) => (far / (near - far), near * far / (near - far)),
+            (DepthMode::Standard, true) => (-T::one(), -near),
+            (DepthMode::Reversed, false) => (near / (far - near), near * far / (far - near)),
+            (DepthMode::Reversed, true) => (T::zero(), near),
+        };
+
+        Matrix4::from([
+            [f / self.aspect, T::zero(), T::zero(), T::zero()],
+            [T::zero(), f, T::zero(), T::zero()],
+            [T::zero(), T::zero(), z, -T::one()],
+            [T::zero(), T::zero(), w, T::zero()],
+        ])
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Orthographic<T> {
+    pub left: T,
+    pub right: T,
+    pub bottom: T,
+    pub top: T,
+    pub near: T,
+    pub far: T,
+}
+
+impl<T: Float> Orthographic<T> {
+    pub fn from_size(size: T, aspect: T, near: T, far: T) -> Self {
+        let two: T = cast(2.0_f32).expect("the value 2 is needed");
+        let half_height = size / two;
+        let half_width = half_height * aspect;
+
+        Self {
+            left: -half_width,
+            right: half_width,
+            bottom: -half_height,
+            top: half_height,
+            near,
+            far,
+        }
+    }
+
+    pub fn set_aspect(&mut self, aspect: T) {
+        let two: T = cast(2.0_f32).expect("the value 2 is needed");
+        let center = (self.left + self.right) / two;
+        let half_width = (self.top - self.bottom) / two * aspect;
+
+        self.left = center - half_width;
+        self.right = center + half_width;
+    }
+}
+
+impl<T: Zero + Float> From<&'_ Orthographic<T>> for Matrix4<T> {
+    fn from(value: &'_ Orthographic<T>) -> Self {
+        assert!(value.near < value.far);
+        assert!(value.left != value.right);
+        assert!(value.bottom != value.top);
+
+        let two: T = cast(2.0_f32).expect("the value 2 is needed");
+
+        let width = value.right - value.left;
+        let height = value.top - value.bottom;
+        let depth = value.far - value.near;
+
+        Matrix4::from([
+            [two / width, T::zero(), T::zero(), T::zero()],
+            [T::zero(), two / height, T::zero(), T::zero()],
+            [T::zero(), T::zero(), -two / depth, T::zero()],
+            [
+                -(value.right + value.left) / width,
+                -(value.top + value.bottom) / height,
+                -(value.far + value.near) / depth,
+                T::one(),
+            ],
+        ])
+    }
+}
+
+impl<T: Zero + Float> From<Orthographic<T>> for Matrix4<T> {
+    fn from(value: Orthographic<T>) -> Self {
+        (&value).into()
+    }
+}
+
+impl<T: Float> Orthographic<T> {
+    pub fn wgpu_matrix(&self, depth: DepthMode) -> Matrix4<T> {
+        assert!(self.near < self.far && self.far.is_finite());
+
+        let mut ans = Matrix4::from(self);
+        let depth_range = self.far - self.near;
+        let (z, w) = match depth {
+            DepthMode::Standard => (-T::one() / depth_range, -self.near / depth_range),
+            DepthMode::Reversed => (T::one() / depth_range, self.far / depth_range),
+        };
+        ans[(2, 2)] = z;
+        ans[(3, 2)] = w;
+        ans
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Projection<T> {
+    Prespective(Prespective<T>),
+    Orthographic(Orthographic<T>),
+}
+
+impl<T: Float> Projection<T> {
+    pub fn set_aspect(&mut self, aspect: T) {
+        match self {
+            Projection::Prespective(p) => p.aspect = aspect,
+            Projection::Orthographic(o) => o.set_aspect(aspect),
+        }
+    }
+
+    pub fn wgpu_matrix(&self, depth: DepthMode) -> Matrix4<T> {
+        match self {
+            Projection::Prespective(p) => p.wgpu_matrix(depth),
+            Projection::Orthographic(o) => o.wgpu_matrix(depth),
+        }
+    }
+}
+
+impl<T> From<Prespective<T>> for Projection<T> {
+    fn from(value: Prespective<T>) -> Self {
+        Projection::Prespective(value)
+    }
+}
+
+impl<T> From<Orthographic<T>> for Projection<T> {
+    fn from(value: Orthographic<T>) -> Self {
+        Projection::Orthographic(value)
+    }
+}
+
+impl<T: Zero + Float> From<&'_ Projection<T>> for Matrix4<T> {
+    fn from(value: &'_ Projection<T>) -> Self {
+        match value {
+            Projection::Prespective(p) => p.into(),
+            Projection::Orthographic(o) => o.into(),
+        }
+    }
+}
+
+impl<T: Zero + Float> From<Projection<T>> for Matrix4<T> {
+    fn from(value: Projection<T>) -> Self {
+        (&value).into()
+    }
+}
+
+// the arrays are columns, so every matrix rotates counter clockwise when multiplied by a vector
 impl<T: Float + Zero + One> Matrix4<T> {
     pub fn rotate_x(angle: T) -> Self {
         Self::from([
             [T::one(), T::zero(), T::zero(), T::zero()],
-            [T::zero(), angle.cos(), -angle.sin(), T::zero()],
-            [T::zero(), angle.sin(), angle.cos(), T::zero()],
+            [T::zero(), angle.cos(), angle.sin(), T::zero()],
+            [T::zero(), -angle.sin(), angle.cos(), T::zero()],
             [T::zero(), T::zero(), T::zero(), T::one()],
         ])
     }
     pub fn rotate_y(angle: T) -> Self {
         Self::from([
-            [angle.cos(), T::zero(), angle.sin(), T::zero()],
+            [angle.cos(), T::zero(), -angle.sin(), T::zero()],
             [T::zero(), T::one(), T::zero(), T::zero()],
-            [-angle.sin(), T::zero(), angle.cos(), T::zero()],
+            [angle.sin(), T::zero(), angle.cos(), T::zero()],
             [T::zero(), T::zero(), T::zero(), T::one()],
         ])
     }
     pub fn rotate_z(angle: T) -> Self {
         Self::from([
-            [angle.cos(), -angle.sin(), T::zero(), T::zero()],
-            [angle.sin(), angle.cos(), T::zero(), T::zero()],
+            [angle.cos(), angle.sin(), T::zero(), T::zero()],
+            [-angle.sin(), angle.cos(), T::zero(), T::zero()],
             [T::zero(), T::zero(), T::one(), T::zero()],
             [T::zero(), T::zero(), T::zero(), T::one()],
         ])
@@ -118,35 +300,265 @@ impl<T: Float + Zero + One> Matrix3<T> {
     pub fn rotate_x(angle: T) -> Self {
         Self::from([
             [T::one(), T::zero(), T::zero()],
-            [T::zero(), angle.cos(), -angle.sin()],
-            [T::zero(), angle.sin(), angle.cos()],
+            [T::zero(), angle.cos(), angle.sin()],
+            [T::zero(), -angle.sin(), angle.cos()],
         ])
     }
     pub fn rotate_y(angle: T) -> Self {
         Self::from([
-            [angle.cos(), T::zero(), angle.sin()],
+            [angle.cos(), T::zero(), -angle.sin()],
             [T::zero(), T::one(), T::zero()],
-            [-angle.sin(), T::zero(), angle.cos()],
+            [angle.sin(), T::zero(), angle.cos()],
         ])
     }
     pub fn rotate_z(angle: T) -> Self {
         Self::from([
-            [angle.cos(), -angle.sin(), T::zero()],
-            [angle.sin(), angle.cos(), T::zero()],
+            [angle.cos(), angle.sin(), T::zero()],
+            [-angle.sin(), angle.cos(), T::zero()],
             [T::zero(), T::zero(), T::one()],
         ])
     }
 }
 
+// the order the rotations are multiplied in, `YXZ` is `rotate_y * rotate_x * rotate_z`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum EulerOrder {
+    XYZ,
+    XZY,
+    #[default]
+    YXZ,
+    YZX,
+    ZXY,
+    ZYX,
+}
+
+impl EulerOrder {
+    pub const ALL: [EulerOrder; 6] = [
+        EulerOrder::XYZ,
+        EulerOrder::XZY,
+        EulerOrder::YXZ,
+        EulerOrder::YZX,
+        EulerOrder::ZXY,
+        EulerOrder::ZYX,
+    ];
+
+    pub fn axes(&self) -> [usize; 3] {
+        match self {
+            EulerOrder::XYZ => [0, 1, 2],
+            EulerOrder::XZY => [0, 2, 1],
+            EulerOrder::YXZ => [1, 0, 2],
+            EulerOrder::YZX => [1, 2, 0],
+            EulerOrder::ZXY => [2, 0, 1],
+            EulerOrder::ZYX => [2, 1, 0],
+        }
+    }
+
+    fn is_cyclic(&self) -> bool {
+        matches!(self, EulerOrder::XYZ | EulerOrder::YZX | EulerOrder::ZXY)
+    }
+}
+
+impl<T: Float + Default + AddAssign> Matrix3<T> {
+    pub fn rotate_axis(axis: usize, angle: T) -> Self {
+        match axis {
+            0 => Self::rotate_x(angle),
+            1 => Self::rotate_y(angle),
+            _ => Self::rotate_z(angle),
+        }
+    }
+
+    pub fn from_euler(euler: &Vector3<T>, order: EulerOrder) -> Self {
+        let [i, j, k] = order.axes();
+        Self::rotate_axis(i, euler[i])
+            * Self::rotate_axis(j, euler[j])
+            * Self::rotate_axis(k, euler[k])
+    }
+
+    // expects a pure rotation, when the middle angle is at +-90 degrees the last angle is set to 0
+    pub fn to_euler(&self, order: EulerOrder) -> Vector3<T> {
+        let [i, j, k] = order.axes();
+        let r = |row: usize, col: usize| self[(col, row)];
+        let s = match order.is_cyclic() {
+            true => T::one(),
+            false => -T::one(),
+        };
+        let threshold: T = cast(1.0 - 1e-6).expect("the threshold is needed");
+
+        let sin_b = (s * r(i, k)).max(-T::one()).min(T::one());
+        let mut ans = Vector3::zeros();
+        ans[j] = sin_b.asin();
+
+        if sin_b.abs() < threshold {
+            ans[i] = (-s * r(j, k)).atan2(r(k, k));
+            ans[k] = (-s * r(i, j)).atan2(r(i, i));
+        } else {
+            ans[i] = (s * r(k, j)).atan2(r(j, j));
+            ans[k] = T::zero();
+        }
+        ans
+    }
+}
+
+impl<T: Float + Default + AddAssign> Matrix4<T> {
+    pub fn from_euler(euler: &Vector3<T>, order: EulerOrder) -> Self {
+        let [a, b, c] = Matrix3::from_euler(euler, order).into_arrays();
+        let zero = T::zero();
+
+        Self::from([
+            [a[0], a[1], a[2], zero],
+            [b[0], b[1], b[2], zero],
+            [c[0], c[1], c[2], zero],
+            [zero, zero, zero, T::one()],
+        ])
+    }
+
+    pub fn to_euler(&self, order: EulerOrder) -> Vector3<T> {
+        let mut rotation = Matrix3::zeros();
+        for (pos, x) in rotation.iter_mut() {
+            *x = self[pos];
+        }
+        rotation.to_euler(order)
+    }
+}
+
+pub struct Decomposition<T> {
+    pub translation: Vector3<T>,
+    pub rotation: Quaternion<T>,
+    pub scale: Vector3<T>,
+    // a mirroring matrix, stored as a negative x scale
+    pub negative_scale: bool,
+}
+
+impl<T: Float + Default + AddAssign> Decomposition<T> {
+    pub fn euler(&self, order: EulerOrder) -> Vector3<T> {
+        self.rotation.to_euler(order)
+    }
+}
+
+impl<T: Float + Default + AddAssign> Matrix4<T> {
+    // splits an affine `translation * rotation * scale` matrix, shear and projection are dropped
+    pub fn decompose(&self) -> Decomposition<T> {
+        let translation = Vector3::from([[self[(3, 0)], self[(3, 1)], self[(3, 2)]]]);
+
+        let mut linear = Matrix3::zeros();
+        for (pos, x) in linear.iter_mut() {
+            *x = self[pos];
+        }
+        let negative_scale = linear.determinant() < T::zero();
+
+        let mut scale = Vector3::zeros();
+        for c in 0..3 {
+            let column = Vector3::from([[linear[(c, 0)], linear[(c, 1)], linear[(c, 2)]]]);
+            scale[c] = column.dot(&column).sqrt();
+        }
+        if negative_scale {
+            scale[0] = -scale[0];
+        }
+
+        for ((c, _), x) in linear.iter_mut() {
+            if !scale[c].is_zero() {
+                *x = *x / scale[c];
+            }
+        }
+
+        Decomposition {
+            translation,
+            rotation: Quaternion::from(&linear),
+            scale,
+            negative_scale,
+        }
+    }
+}
+
 impl Vector3<f32> {
     pub fn euler_into_rotation_matrix3(&self) -> Matrix3<f32> {
-        Matrix3::rotate_y(*self.y())
-        * Matrix3::rotate_x(*self.x())
-        * Matrix3::rotate_x(*self.z())
+        Matrix3::from_euler(self, EulerOrder::default())
     }
     pub fn euler_into_rotation_matrix4(&self) -> Matrix4<f32> {
-        Matrix4::rotate_y(*self.y())
-        * Matrix4::rotate_x(*self.x())
-        * Matrix4::rotate_x(*self.z())
+        Matrix4::from_euler(self, EulerOrder::default())
+    }
+}
+
+#[test]
+fn test_orthographic() {
+    let ortho = Orthographic::from_size(4.0_f32, 2.0, 1.0, 11.0);
+    assert_eq!(
+        (ortho.left, ortho.right, ortho.bottom, ortho.top),
+        (-4.0, 4.0, -2.0, 2.0)
+    );
+
+    let m = Matrix4::from(&Projection::from(ortho));
+    let near = &m * super::matrices::Vector4::from([[4.0, 2.0, -1.0, 1.0]]);
+    let far = &m * super::matrices::Vector4::from([[-4.0, -2.0, -11.0, 1.0]]);
+
+    assert_eq!(near.into_arrays(), [[1.0, 1.0, -1.0, 1.0]]);
+    assert_eq!(far.into_arrays(), [[-1.0, -1.0, 1.0, 1.0]]);
+}
+
+#[test]
+fn test_wgpu_prespective() {
+    use super::matrices::Vector4;
+
+    let depth_of = |m: &Matrix4<f64>, z: f64| {
+        let v = m * Vector4::from([[0.0, 0.0, z, 1.0]]);
+        v[2] / v[3]
+    };
+    let mut p = Prespective {
+        fovy_rad: 1.0_f64,
+        aspect: 1.5,
+        near: 0.5,
+        far: 100.0,
+    };
+
+    let standard = p.wgpu_matrix(DepthMode::Standard);
+    let reversed = p.wgpu_matrix(DepthMode::Reversed);
+    assert!(depth_of(&standard, -0.5).abs() < 1e-12);
+    assert!((depth_of(&standard, -100.0) - 1.0).abs() < 1e-12);
+    assert!((depth_of(&reversed, -0.5) - 1.0).abs() < 1e-12);
+    assert!(depth_of(&reversed, -100.0).abs() < 1e-12);
+
+    let opengl = Matrix4::from(&p);
+    let v = &opengl * Vector4::from([[0.0, 0.0, -10.0, 1.0]]);
+    assert!(((v[2] / v[3] + 1.0) / 2.0 - depth_of(&standard, -10.0)).abs() < 1e-12);
+
+    p.far = f64::INFINITY;
+    let standard = p.wgpu_matrix(DepthMode::Standard);
+    let reversed = p.wgpu_matrix(DepthMode::Reversed);
+    assert!(depth_of(&standard, -0.5).abs() < 1e-12);
+    assert!((depth_of(&reversed, -0.5) - 1.0).abs() < 1e-12);
+    assert!(depth_of(&reversed, -1e12) < 1e-9);
+    assert!(depth_of(&reversed, -1e12) > 0.0);
+}
+
+#[test]
+fn test_euler_round_trip() {
+    use super::quaternions::Quaternion;
+
+    let angles = [
+        Vector3::from([[0.3_f64, -1.1, 2.5]]),
+        Vector3::from([[std::f64::consts::FRAC_PI_2, 0.7, -0.4]]),
+        Vector3::from([[0.2, std::f64::consts::FRAC_PI_2, 0.9]]),
+        Vector3::from([[-0.5, -std::f64::consts::FRAC_PI_2, 0.0]]),
+    ];
+
+    for order in EulerOrder::ALL {
+        for euler in &angles {
+            let m = Matrix3::from_euler(euler, order);
+            let back = Matrix3::from_euler(&m.to_euler(order), order);
+            let q = Matrix3::from(Quaternion::from_euler_ordered(euler, order));
+            let from_q = Quaternion::from_euler_ordered(euler, order).to_euler(order);
+            let from_q = Matrix3::from_euler(&from_q, order);
+
+            for (pos, x) in m.iter() {
+                assert!((x - back[pos]).abs() < 1e-9, "{order:?} {euler:?}");
+                assert!((x - q[pos]).abs() < 1e-9, "{order:?} {euler:?}");
+                assert!((x - from_q[pos]).abs() < 1e-9, "{order:?} {euler:?}");
+            }
+        }
     }
+
+    let roll = Vector3::from([[0.0_f32, 0.0, std::f32::consts::FRAC_PI_2]]);
+    let v = roll.euler_into_rotation_matrix3() * Vector3::from([[1.0, 0.0, 0.0]]);
+    assert!(v[0].abs() < 1e-6 && (v[1] - 1.0).abs() < 1e-6);
 }
@@ -237,7 +237,3 @@ impl<T: From<f32>> Vector3<T> {
         Vector3::<T>::from([[T::from(0.0), T::from(1.0), T::from(0.0)]])
     }
 }
-
-trait Quaternion<T>: Vector4D<T> {}
-
-impl<T> Quaternion<T> for Matrix<T, 4, 1> {}
@@ -0,0 +1,282 @@
+use std::{marker::PhantomData, num::NonZeroU64};
+
+use bytemuck::{Pod, Zeroable};
+use wgpu::{
+    BindGroupEntry, BindGroupLayoutEntry, BindingResource, BindingType, Buffer, BufferAddress,
+    BufferBinding, BufferBindingType, BufferDescriptor, BufferUsages, Device, Queue, ShaderStages,
+};
+
+use super::{bind_groups::BindDataEntry, buffers::BufferContainer, uploader::Uploader};
+
+// one `T` bound as a uniform, `T` has to follow the uniform layout rules of the shader
+pub struct UniformBuffer<T: Pod + Zeroable> {
+    buffer: BufferContainer<T>,
+}
+
+impl<T: Pod + Zeroable> UniformBuffer<T> {
+    pub fn new(value: &T, device: &Device, queue: &Queue) -> Self {
+        Self {
+            buffer: BufferContainer::create_buffer(
+                value,
+                device,
+                queue,
+                BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+                false,
+            ),
+        }
+    }
+
+    pub fn buffer(&self) -> &BufferContainer<T> {
+        &self.buffer
+    }
+
+    pub fn write(&self, uploader: &mut Uploader, value: &T) {
+        uploader.write(&self.buffer, 0..1, std::slice::from_ref(value));
+    }
+}
+
+impl<T: Pod + Zeroable> BindDataEntry for UniformBuffer<T> {
+    type Args<'a> = &'a Self;
+
+    fn layout_entries(binding: u32) -> Box<dyn Iterator<Item = BindGroupLayoutEntry>> {
+        Box::new(std::iter::once(buffer_layout_entry::<T>(
+            binding,
+            ShaderStages::VERTEX_FRAGMENT,
+            BufferBindingType::Uniform,
+            false,
+        )))
+    }
+
+    fn entries<'a>(
+        binding: u32,
+        args: Self::Args<'a>,
+    ) -> Box<dyn Iterator<Item = BindGroupEntry<'a>> + 'a> {
+        Box::new(std::iter::once(BindGroupEntry {
+            binding,
+            resource: args.buffer.buffer().as_entire_binding(),
+        }))
+    }
+}
+
+// an array of `T` bound as `array<T>`, read only unless `READ_ONLY` is false
+pub struct StorageBuffer<T: Pod + Zeroable, const READ_ONLY: bool = true> {
+    buffer: BufferContainer<T>,
+}
+
+pub type ReadWriteStorageBuffer<T> = StorageBuffer<T, false>;
+
+impl<T: Pod + Zeroable, const READ_ONLY: bool> StorageBuffer<T, READ_ONLY> {
+    // `COPY_DST` and `STORAGE` are always added to `usage`
+    pub fn new(data: &[T], device: &Device, queue: &Queue, usage: BufferUsages) -> Self {
+        assert!(
+            !data.is_empty(),
+            "a storage buffer needs at least one element"
+        );
+        Self {
+            buffer: BufferContainer::create_buffer(
+                &data,
+                device,
+                queue,
+                usage | BufferUsages::STORAGE | BufferUsages::COPY_DST,
+                false,
+            ),
+        }
+    }
+
+    pub fn buffer(&self) -> &BufferContainer<T> {
+        &self.buffer
+    }
+
+    pub fn len(&self) -> u64 {
+        self.buffer.size()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buffer.size() == 0
+    }
+
+    // writes `data` starting at the element `start`
+    pub fn write(&self, uploader: &mut Uploader, start: u64, data: &[T]) {
+        let end = start + data.len() as u64;
+        assert!(end <= self.len(), "the write is out of the buffer");
+        uploader.write(&self.buffer, start..end, data);
+    }
+}
+
+impl<T: Pod + Zeroable, const READ_ONLY: bool> BindDataEntry for StorageBuffer<T, READ_ONLY> {
+    type Args<'a> = &'a Self;
+
+    fn layout_entries(binding: u32) -> Box<dyn Iterator<Item = BindGroupLayoutEntry>> {
+        // vertex shaders can't write to storage buffers without `VERTEX_WRITABLE_STORAGE`
+        let visibility = if READ_ONLY {
+            ShaderStages::VERTEX_FRAGMENT | ShaderStages::COMPUTE
+        } else {
+            ShaderStages::FRAGMENT | ShaderStages::COMPUTE
+        };
+        Box::new(std::iter::once(buffer_layout_entry::<T>(
+            binding,
+            visibility,
+            BufferBindingType::Storage {
+                read_only: READ_ONLY,
+            },
+            false,
+        )))
+    }
+
+    fn entries<'a>(
+        binding: u32,
+        args: Self::Args<'a>,
+    ) -> Box<dyn Iterator<Item = BindGroupEntry<'a>> + 'a> {
+        Box::new(std::iter::once(BindGroupEntry {
+            binding,
+            resource: args.buffer.buffer().as_entire_binding(),
+        }))
+    }
+}
+
+// `len` uniforms of `T` in one buffer, each aligned to `min_uniform_buffer_offset_alignment`.
+// the group binds one of them, chosen by passing `offset(index)` as the dynamic offset
+pub struct DynamicUniform<T: Pod + Zeroable> {
+    marker: PhantomData<T>,
+    buffer: Buffer,
+    stride: BufferAddress,
+    len: u64,
+}
+
+impl<T: Pod + Zeroable> DynamicUniform<T> {
+    pub fn new(len: u64, device: &Device) -> Self {
+        // `write` copies `size_of::<T>()` bytes, which has to stay 4 byte aligned
+        assert!(
+            (std::mem::size_of::<T>() as BufferAddress) % wgpu::COPY_BUFFER_ALIGNMENT == 0,
+            "the uniform size has to be a multiple of 4 bytes"
+        );
+        let stride = aligned_stride(
+            std::mem::size_of::<T>() as BufferAddress,
+            device.limits().min_uniform_buffer_offset_alignment as BufferAddress,
+        );
+        Self {
+            marker: PhantomData,
+            buffer: device.create_buffer(&BufferDescriptor {
+                label: Some("dynamic uniform buffer"),
+                size: stride * len.max(1),
+                usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }),
+            stride,
+            len,
+        }
+    }
+
+    pub fn buffer(&self) -> &Buffer {
+        &self.buffer
+    }
+
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn stride(&self) -> BufferAddress {
+        self.stride
+    }
+
+    // the dynamic offset that selects the uniform at `index`
+    pub fn offset(&self, index: u64) -> u32 {
+        assert!(index < self.len, "the index is out of the buffer");
+        (index * self.stride) as u32
+    }
+
+    pub fn write(&self, uploader: &mut Uploader, index: u64, value: &T) {
+        uploader.write_bytes(
+            &self.buffer,
+            self.offset(index) as BufferAddress,
+            bytemuck::bytes_of(value),
+        );
+    }
+}
+
+impl<T: Pod + Zeroable> BindDataEntry for DynamicUniform<T> {
+    type Args<'a> = &'a Self;
+
+    fn layout_entries(binding: u32) -> Box<dyn Iterator<Item = BindGroupLayoutEntry>> {
+        Box::new(std::iter::once(buffer_layout_entry::<T>(
+            binding,
+            ShaderStages::VERTEX_FRAGMENT,
+            BufferBindingType::Uniform,
+            true,
+        )))
+    }
+
+    fn entries<'a>(
+        binding: u32,
+        args: Self::Args<'a>,
+    ) -> Box<dyn Iterator<Item = BindGroupEntry<'a>> + 'a> {
+        Box::new(std::iter::once(BindGroupEntry {
+            binding,
+            resource: BindingResource::Buffer(BufferBinding {
+                buffer: &args.buffer,
+                offset: 0,
+                size: NonZeroU64::new(std::mem::size_of::<T>() as u64),
+            }),
+        }))
+    }
+}
+
+fn buffer_layout_entry<T>(
+    binding: u32,
+    visibility: ShaderStages,
+    ty: BufferBindingType,
+    has_dynamic_offset: bool,
+) -> BindGroupLayoutEntry {
+    BindGroupLayoutEntry {
+        binding,
+        visibility,
+        ty: BindingType::Buffer {
+            ty,
+            has_dynamic_offset,
+            min_binding_size: NonZeroU64::new(std::mem::size_of::<T>() as u64),
+        },
+        count: None,
+    }
+}
+
+fn aligned_stride(size: BufferAddress, alignment: BufferAddress) -> BufferAddress {
+    size.max(1).div_ceil(alignment) * alignment
+}
+
+#[test]
+fn test_bind_entries() {
+    use super::{bind_groups::BindData, texture::MatrixTexture};
+
+    assert_eq!(aligned_stride(64, 256), 256);
+    assert_eq!(aligned_stride(256, 256), 256);
+    assert_eq!(aligned_stride(300, 256), 512);
+
+    // the texture takes two bindings so the buffers start at 2
+    let entries = <(
+        MatrixTexture,
+        UniformBuffer<[f32; 4]>,
+        ReadWriteStorageBuffer<u32>,
+        DynamicUniform<[f32; 16]>,
+    )>::layout_entries();
+    let bindings = entries.iter().map(|x| x.binding).collect::<Vec<_>>();
+    assert_eq!(bindings, [0, 1, 2, 3, 4]);
+    assert!(matches!(
+        entries[3].ty,
+        BindingType::Buffer {
+            ty: BufferBindingType::Storage { read_only: false },
+            ..
+        }
+    ));
+    assert!(matches!(
+        entries[4].ty,
+        BindingType::Buffer {
+            has_dynamic_offset: true,
+            min_binding_size: Some(size),
+            ..
+        } if size.get() == 64
+    ));
+}
@@ -6,21 +6,31 @@ use wgpu::{
 
 use super::texture::MatrixTexture;
 
+// `binding` is the first binding of the entry, inside a tuple every entry starts after the
+// `BINDINGS` of the entries before it
 pub trait BindDataEntry {
     type Args<'a>;
 
-    fn layout_entries() -> Box<dyn Iterator<Item = BindGroupLayoutEntry>>;
+    // the number of bindings used by the entry
+    const BINDINGS: u32 = 1;
 
-    fn entries<'a>(args: Self::Args<'a>) -> Box<dyn Iterator<Item = BindGroupEntry<'a>> + 'a>;
+    fn layout_entries(binding: u32) -> Box<dyn Iterator<Item = BindGroupLayoutEntry>>;
+
+    fn entries<'a>(
+        binding: u32,
+        args: Self::Args<'a>,
+    ) -> Box<dyn Iterator<Item = BindGroupEntry<'a>> + 'a>;
 }
 
 impl BindDataEntry for MatrixTexture {
     type Args<'a> = &'a Self;
 
-    fn layout_entries() -> Box<dyn Iterator<Item = BindGroupLayoutEntry>> {
+    const BINDINGS: u32 = 2;
+
+    fn layout_entries(binding: u32) -> Box<dyn Iterator<Item = BindGroupLayoutEntry>> {
         Box::new(
             std::iter::once(BindGroupLayoutEntry {
-                binding: 0,
+                binding,
                 visibility: ShaderStages::FRAGMENT,
                 ty: wgpu::BindingType::Texture {
                     view_dimension: wgpu::TextureViewDimension::D2,
@@ -30,7 +40,7 @@ impl BindDataEntry for MatrixTexture {
                 count: None,
             })
             .chain(std::iter::once(BindGroupLayoutEntry {
-                binding: 1,
+                binding: binding + 1,
                 visibility: ShaderStages::FRAGMENT,
                 ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
                 count: None,
@@ -38,14 +48,17 @@ impl BindDataEntry for MatrixTexture {
         )
     }
 
-    fn entries<'a>(args: Self::Args<'a>) -> Box<dyn Iterator<Item = BindGroupEntry<'a>> + 'a> {
+    fn entries<'a>(
+        binding: u32,
+        args: Self::Args<'a>,
+    ) -> Box<dyn Iterator<Item = BindGroupEntry<'a>> + 'a> {
         Box::new(
             std::iter::once(BindGroupEntry {
-                binding: 0,
+                binding,
                 resource: wgpu::BindingResource::TextureView(args.view()),
             })
             .chain(std::iter::once(BindGroupEntry {
-                binding: 1,
+                binding: binding + 1,
                 resource: wgpu::BindingResource::Sampler(args.sampler()),
             })),
         )
@@ -54,6 +67,8 @@ impl BindDataEntry for MatrixTexture {
 pub trait BindData {
     type Args<'a>;
 
+    fn layout_entries() -> Vec<BindGroupLayoutEntry>;
+
     fn create_layout(label: &str, device: &Device) -> BindGroupLayoutContainer<Self>
     where
         Self: Sized;
@@ -72,13 +87,23 @@ macro_rules! impl_bind_data {
         impl<$($t:BindDataEntry,)+> BindData for ($($t,)+) {
             type Args<'a> = ($($t::Args<'a>,)+);
 
+            #[allow(unused_assignments)]
+            fn layout_entries() -> Vec<BindGroupLayoutEntry> {
+                let mut binding = 0;
+                let mut entries = Vec::new();
+                $(
+                    entries.extend($t::layout_entries(binding));
+                    binding += $t::BINDINGS;
+                )+
+                entries
+            }
+
             fn create_layout(label:&str,device: &Device) -> BindGroupLayoutContainer<Self>
             where
                 Self: Sized {
                     let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor{
                         label:Some(label),
-                        entries: &([$($t::layout_entries(),)+].into_iter().flatten().collect::<Vec<_>>())
-
+                        entries: &Self::layout_entries(),
                     });
                     BindGroupLayoutContainer {
                         marker: PhantomData,
@@ -86,17 +111,23 @@ macro_rules! impl_bind_data {
                     }
                 }
 
-            #[allow(non_snake_case)]
+            #[allow(non_snake_case, unused_assignments)]
             fn create_group(device: &Device, layout: &BindGroupLayoutContainer<Self>,args: Self::Args<'_>) -> BindGroupContainer<Self>
             where
                 Self: Sized{
                 let ($($t,)+) = args;
+                let mut binding = 0;
+                let mut entries = Vec::new();
+                $(
+                    entries.extend($t::entries(binding, $t));
+                    binding += $t::BINDINGS;
+                )+
                 BindGroupContainer {
                     marker: PhantomData,
                     group:
                     device.create_bind_group(&wgpu::BindGroupDescriptor {
                         layout: layout.layout(),
-                        entries: &([$($t::entries($t),)+].into_iter().flatten().collect::<Vec<_>>()),
+                        entries: &entries,
                         label: Some("tuple group"),
                     })
                 }
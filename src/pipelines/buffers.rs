@@ -1,12 +1,22 @@
-use std::marker::PhantomData;
+use std::{
+    borrow::Cow,
+    future,
+    marker::PhantomData,
+    ops::RangeBounds,
+    sync::{Arc, Mutex},
+    task::{Poll, Waker},
+};
 
 use bytemuck::{Pod, Zeroable};
 use matrix_engine::impl_all;
 use wgpu::{
-    Buffer, BufferAddress, BufferDescriptor, BufferUsages, Device, Queue, RenderPass,
-    VertexAttribute, VertexBufferLayout,
+    Buffer, BufferAddress, BufferAsyncError, BufferDescriptor, BufferUsages, CommandEncoder,
+    CommandEncoderDescriptor, Device, IndexFormat, MapMode, Queue, RenderPass, VertexBufferLayout,
+    COPY_BUFFER_ALIGNMENT,
 };
 
+pub use matrix_renderer_derive::Bufferable;
+
 pub struct BufferContainer<T: Pod + Zeroable> {
     marker: PhantomData<T>,
     buffer: Buffer,
@@ -36,14 +46,15 @@ impl<T: Pod + Zeroable> BufferContainer<T> {
         usage: BufferUsages,
         map: bool,
     ) -> BufferContainer<T> {
-        let bytes = data.get_bytes();
+        // `write_buffer` needs a multiple of 4 bytes, `size` stays the real element count
+        let bytes = padded_bytes(data.get_bytes());
         let buffer = device.create_buffer(&BufferDescriptor {
             label: Some("veretex buffer"),
             size: bytes.len() as u64,
             usage,
             mapped_at_creation: map,
         });
-        queue.write_buffer(&buffer, 0, bytes);
+        queue.write_buffer(&buffer, 0, &bytes);
         // let buffer = device.create_buffer_init(&BufferInitDescriptor {
         //     label: Some("vertex buffer"),
         //     contents: ,
@@ -75,56 +86,133 @@ impl<T: Pod + Zeroable> BufferContainer<T> {
         }
     }
 
+    // the copy is recorded in `encoder`, after whatever writes to `self` it already holds
     pub fn clone_data_with_size(
         &self,
         device: &Device,
-        queue: &Queue,
+        encoder: &mut CommandEncoder,
         new_obj_len: u64,
         label: &str,
     ) -> Self {
+        assert!(
+            self.buffer.usage().contains(BufferUsages::COPY_SRC),
+            "a buffer needs COPY_SRC to be cloned"
+        );
         let t_size = std::mem::size_of::<T>() as u64;
 
         let buffer = device.create_buffer(&BufferDescriptor {
             label: Some(label),
             size: (new_obj_len * t_size),
-            usage: self.buffer.usage(),
+            usage: self.buffer.usage() | BufferUsages::COPY_DST,
             mapped_at_creation: false,
         });
         let write_size = (new_obj_len).min(self.size);
 
-        queue.write_buffer(
-            &buffer,
-            0,
-            &self
-                .buffer
-                .slice(0..(t_size * write_size))
-                .get_mapped_range(),
-        );
-
-        if write_size < new_obj_len {
-            let mut v = Vec::new();
-            v.resize(((new_obj_len - write_size) * t_size) as usize, 0);
-            queue.write_buffer(&buffer, write_size * t_size, &v);
+        // new buffers are zeroed, so only the kept part is copied
+        if write_size > 0 {
+            encoder.copy_buffer_to_buffer(&self.buffer, 0, &buffer, 0, write_size * t_size);
         }
         Self::new(buffer, new_obj_len)
     }
 
+    // copies the buffer into a `MAP_READ` staging buffer and maps it, the buffer needs `COPY_SRC`.
+    // like `map_read` it only resolves once the device was polled after the copy
+    pub async fn read_to_vec(
+        &self,
+        device: &Device,
+        queue: &Queue,
+    ) -> Result<Vec<T>, BufferAsyncError> {
+        let size = self.size * std::mem::size_of::<T>() as BufferAddress;
+        let bytes = read_buffer(device, queue, &self.buffer, size, "buffer readback").await?;
+        let mut data = vec![T::zeroed(); self.size as usize];
+        bytemuck::cast_slice_mut(&mut data).copy_from_slice(&bytes);
+        Ok(data)
+    }
+
     pub(crate) fn usage(&self) -> BufferUsages {
         self.buffer.usage()
     }
 }
 
+// copies `size` bytes of `source` into a staging buffer, maps it and resolves once the map
+// finished
+pub(crate) async fn read_buffer(
+    device: &Device,
+    queue: &Queue,
+    source: &Buffer,
+    size: BufferAddress,
+    label: &str,
+) -> Result<Vec<u8>, BufferAsyncError> {
+    assert!(
+        source.usage().contains(BufferUsages::COPY_SRC),
+        "a buffer needs COPY_SRC to be read back"
+    );
+    if size == 0 {
+        return Ok(Vec::new());
+    }
+    let staging = device.create_buffer(&BufferDescriptor {
+        label: Some(label),
+        size,
+        usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+    let mut encoder =
+        device.create_command_encoder(&CommandEncoderDescriptor { label: Some(label) });
+    encoder.copy_buffer_to_buffer(source, 0, &staging, 0, size);
+    queue.submit(std::iter::once(encoder.finish()));
+
+    map_read(&staging, ..).await
+}
+
+#[derive(Default)]
+struct MapState {
+    result: Option<Result<(), BufferAsyncError>>,
+    waker: Option<Waker>,
+}
+
+// resolves from the map callback without blocking. on native that callback only runs when the
+// device is polled, which `Queue::submit` does too, so the caller has to keep submitting or
+// poll with `Maintain::Poll`/`Maintain::Wait` while it waits. on the web the browser runs it
+pub(crate) async fn map_read(
+    buffer: &Buffer,
+    range: impl RangeBounds<BufferAddress>,
+) -> Result<Vec<u8>, BufferAsyncError> {
+    let slice = buffer.slice(range);
+    let state = Arc::new(Mutex::new(MapState::default()));
+    let callback_state = state.clone();
+    slice.map_async(MapMode::Read, move |result| {
+        let mut state = callback_state.lock().unwrap();
+        state.result = Some(result);
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+    });
+    future::poll_fn(|cx| {
+        let mut state = state.lock().unwrap();
+        match state.result.take() {
+            Some(result) => Poll::Ready(result),
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    })
+    .await?;
+
+    let data = slice.get_mapped_range().to_vec();
+    buffer.unmap();
+    Ok(data)
+}
+
 #[repr(C)]
-#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable, Bufferable)]
 pub struct Vertex {
+    #[location(0)]
     pub position: [f32; 3],
+    #[location(1)]
     pub texture_pos: [f32; 2],
 }
 
-impl Vertex {
-    const ATTRS: [VertexAttribute; 2] = wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x3];
-}
-
 pub trait IntoBytes<T: Pod + Zeroable> {
     fn get_bytes(&self) -> &[u8];
     fn size(&self) -> usize;
@@ -166,52 +254,120 @@ impl<T: Pod + Zeroable> IntoBytes<T> for &'_ [T] {
         <[T]>::len(self)
     }
 }
-pub enum BufferType {
-    Vertex(u32),
-    Index,
+fn padded_bytes(bytes: &[u8]) -> Cow<'_, [u8]> {
+    let len = bytes.len() as BufferAddress;
+    let padded = len.div_ceil(COPY_BUFFER_ALIGNMENT) * COPY_BUFFER_ALIGNMENT;
+    if padded == len {
+        return Cow::Borrowed(bytes);
+    }
+    let mut ans = bytes.to_vec();
+    ans.resize(padded as usize, 0);
+    Cow::Owned(ans)
 }
 
 pub trait Bufferable: Pod + Zeroable {
     fn describe<'a>() -> VertexBufferLayout<'a>;
 
-    fn apply_to_pass<'a>(
-        data: &mut RenderPass<'a>,
-        buffer: &'a BufferContainer<Self>,
-        buffer_type: BufferType,
-    ) {
-        match buffer_type {
-            BufferType::Vertex(slot) => {
-                RenderPass::set_vertex_buffer(data, slot, buffer.buffer().slice(..));
-            }
-            BufferType::Index => RenderPass::set_index_buffer(
-                data,
-                buffer.buffer().slice(..),
-                wgpu::IndexFormat::Uint16,
-            ),
-        };
+    fn apply_to_pass<'a>(data: &mut RenderPass<'a>, buffer: &'a BufferContainer<Self>, slot: u32) {
+        RenderPass::set_vertex_buffer(data, slot, buffer.buffer().slice(..));
+    }
+}
+
+pub trait Index: Pod + Zeroable {
+    const FORMAT: IndexFormat;
+
+    fn into_index_buffer(buffer: BufferContainer<Self>) -> IndexBuffer;
+}
+
+impl Index for u16 {
+    const FORMAT: IndexFormat = IndexFormat::Uint16;
+
+    fn into_index_buffer(buffer: BufferContainer<Self>) -> IndexBuffer {
+        IndexBuffer::U16(buffer)
+    }
+}
+
+impl Index for u32 {
+    const FORMAT: IndexFormat = IndexFormat::Uint32;
+
+    fn into_index_buffer(buffer: BufferContainer<Self>) -> IndexBuffer {
+        IndexBuffer::U32(buffer)
     }
 }
 
-impl Bufferable for Vertex {
-    fn describe<'a>() -> VertexBufferLayout<'a> {
-        VertexBufferLayout {
-            array_stride: std::mem::size_of::<Vertex>() as BufferAddress,
-            step_mode: wgpu::VertexStepMode::Vertex,
-            attributes: &Self::ATTRS,
+// the format is taken from the index type, so a mesh can use either without the pipeline knowing
+pub enum IndexBuffer {
+    U16(BufferContainer<u16>),
+    U32(BufferContainer<u32>),
+}
+
+impl<I: Index> From<BufferContainer<I>> for IndexBuffer {
+    fn from(value: BufferContainer<I>) -> Self {
+        I::into_index_buffer(value)
+    }
+}
+
+impl IndexBuffer {
+    // uses 16 bit indices when every index fits in them and 32 bit ones otherwise
+    pub fn create(
+        indices: &[u32],
+        device: &Device,
+        queue: &Queue,
+        usage: BufferUsages,
+    ) -> IndexBuffer {
+        match index_format_for(indices) {
+            IndexFormat::Uint16 => {
+                let indices = indices.iter().map(|x| *x as u16).collect::<Vec<_>>();
+                BufferContainer::create_buffer(&indices.as_slice(), device, queue, usage, false)
+                    .into()
+            }
+            IndexFormat::Uint32 => {
+                BufferContainer::create_buffer(&indices, device, queue, usage, false).into()
+            }
+        }
+    }
+
+    pub fn buffer(&self) -> &Buffer {
+        match self {
+            IndexBuffer::U16(x) => x.buffer(),
+            IndexBuffer::U32(x) => x.buffer(),
+        }
+    }
+
+    pub fn format(&self) -> IndexFormat {
+        match self {
+            IndexBuffer::U16(_) => u16::FORMAT,
+            IndexBuffer::U32(_) => u32::FORMAT,
+        }
+    }
+
+    pub fn size(&self) -> u64 {
+        match self {
+            IndexBuffer::U16(x) => x.size(),
+            IndexBuffer::U32(x) => x.size(),
         }
     }
+
+    pub fn apply_to_pass<'a>(&'a self, pass: &mut RenderPass<'a>) {
+        pass.set_index_buffer(self.buffer().slice(..), self.format());
+    }
+}
+
+pub fn index_format_for(indices: &[u32]) -> IndexFormat {
+    if indices.iter().all(|x| *x <= u16::MAX as u32) {
+        IndexFormat::Uint16
+    } else {
+        IndexFormat::Uint32
+    }
 }
 
 pub struct VertexBuffer<Vertex: Bufferable> {
     buffer: BufferContainer<Vertex>,
-    index_buffer: Option<BufferContainer<u16>>,
+    index_buffer: Option<IndexBuffer>,
 }
 
 impl<Vertex: Bufferable> VertexBuffer<Vertex> {
-    pub fn new(
-        buffer: BufferContainer<Vertex>,
-        index_buffer: Option<BufferContainer<u16>>,
-    ) -> Self {
+    pub fn new(buffer: BufferContainer<Vertex>, index_buffer: Option<IndexBuffer>) -> Self {
         Self {
             buffer,
             index_buffer,
@@ -222,7 +378,7 @@ impl<Vertex: Bufferable> VertexBuffer<Vertex> {
         &self.buffer
     }
 
-    pub fn index_buffer(&self) -> Option<&BufferContainer<u16>> {
+    pub fn index_buffer(&self) -> Option<&IndexBuffer> {
         self.index_buffer.as_ref()
     }
     pub fn size(&self) -> u64 {
@@ -252,3 +408,52 @@ macro_rules! impl_buffer_group {
 }
 
 impl_all!(impl_buffer_group);
+
+#[test]
+fn test_derived_layout() {
+    let layout = Vertex::describe();
+    assert_eq!(layout.array_stride, 20);
+    assert_eq!(layout.step_mode, wgpu::VertexStepMode::Vertex);
+    assert_eq!(
+        layout.attributes,
+        &wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x2]
+    );
+
+    #[repr(C)]
+    #[derive(Clone, Copy, Pod, Zeroable, Bufferable)]
+    #[step_mode(instance)]
+    struct Instance {
+        #[location(3)]
+        matrix: [[f32; 3]; 3],
+        id: u32,
+        color: [u8; 4],
+    }
+    let layout = Instance::describe();
+    assert_eq!(layout.step_mode, wgpu::VertexStepMode::Instance);
+    assert_eq!(
+        layout.attributes,
+        &wgpu::vertex_attr_array![
+            3 => Float32x3,
+            4 => Float32x3,
+            5 => Float32x3,
+            6 => Uint32,
+            7 => Uint8x4
+        ]
+    );
+}
+
+#[test]
+fn test_index_format() {
+    assert_eq!(index_format_for(&[0, 2, 1, 0, 3, 2]), IndexFormat::Uint16);
+    assert_eq!(index_format_for(&[0, 65_535]), IndexFormat::Uint16);
+    assert_eq!(index_format_for(&[0, 65_536]), IndexFormat::Uint32);
+    assert_eq!(<u32 as Index>::FORMAT, IndexFormat::Uint32);
+
+    // a single u16 triangle is 6 bytes, the buffer gets 8
+    let triangle: &[u16] = &[0, 1, 2];
+    let bytes = padded_bytes(IntoBytes::<u16>::get_bytes(triangle));
+    assert_eq!(bytes.len(), 8);
+    assert_eq!(&bytes[..6], bytemuck::cast_slice::<u16, u8>(triangle));
+    assert_eq!(IntoBytes::<u16>::size(triangle), 3);
+    assert!(matches!(padded_bytes(&[0; 12]), Cow::Borrowed(_)));
+}
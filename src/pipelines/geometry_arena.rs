@@ -0,0 +1,259 @@
+use std::{ops::Range, sync::Arc};
+
+use bytemuck::{Pod, Zeroable};
+use wgpu::{BufferAddress, BufferUsages, Device, RenderPass};
+
+use super::{
+    buffers::{BufferContainer, Bufferable, Index},
+    uploader::Uploader,
+};
+
+// the cpu side of a mesh, indices are relative to the first vertex of the mesh
+pub struct MeshData<V> {
+    pub vertices: Vec<V>,
+    pub indices: Vec<u32>,
+}
+
+impl<V> MeshData<V> {
+    // a mesh without an index buffer, drawn in vertex order
+    pub fn unindexed(vertices: Vec<V>) -> Self {
+        let indices = (0..vertices.len() as u32).collect();
+        Self { vertices, indices }
+    }
+}
+
+// where a mesh lives in a `GeometryArena`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MeshRange {
+    vertices: Range<u64>,
+    indices: Range<u64>,
+}
+
+impl MeshRange {
+    pub fn base_vertex(&self) -> i32 {
+        self.vertices.start as i32
+    }
+
+    pub fn first_index(&self) -> u32 {
+        self.indices.start as u32
+    }
+
+    pub fn count(&self) -> u32 {
+        (self.indices.end - self.indices.start) as u32
+    }
+
+    // the index range to pass to `draw_indexed` together with `base_vertex`
+    pub fn index_range(&self) -> Range<u32> {
+        self.indices.start as u32..self.indices.end as u32
+    }
+}
+
+// first fit allocation over `0..len`, freed ranges are merged with their neighbours
+#[derive(Debug, Default)]
+pub struct FreeList {
+    // sorted and never adjacent
+    free: Vec<Range<u64>>,
+    len: u64,
+}
+
+impl FreeList {
+    pub fn new(len: u64) -> Self {
+        let mut list = Self::default();
+        list.grow(len);
+        list
+    }
+
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn free_len(&self) -> u64 {
+        self.free.iter().map(|x| x.end - x.start).sum()
+    }
+
+    pub fn allocate(&mut self, len: u64) -> Option<Range<u64>> {
+        if len == 0 {
+            return Some(0..0);
+        }
+        let i = self.free.iter().position(|x| x.end - x.start >= len)?;
+        let range = &mut self.free[i];
+        let start = range.start;
+        range.start += len;
+        if range.is_empty() {
+            self.free.remove(i);
+        }
+        Some(start..start + len)
+    }
+
+    pub fn free(&mut self, range: Range<u64>) {
+        if range.is_empty() {
+            return;
+        }
+        let i = self.free.partition_point(|x| x.start < range.start);
+        debug_assert!(
+            self.free.get(i).map_or(true, |x| range.end <= x.start)
+                && (i == 0 || self.free[i - 1].end <= range.start),
+            "freed a range that is already free"
+        );
+        let merge_prev = i > 0 && self.free[i - 1].end == range.start;
+        let merge_next = self.free.get(i).is_some_and(|x| x.start == range.end);
+        match (merge_prev, merge_next) {
+            (true, true) => {
+                self.free[i - 1].end = self.free[i].end;
+                self.free.remove(i);
+            }
+            (true, false) => self.free[i - 1].end = range.end,
+            (false, true) => self.free[i].start = range.start,
+            (false, false) => self.free.insert(i, range),
+        }
+    }
+
+    // adds `self.len..len` to the free ranges
+    pub fn grow(&mut self, len: u64) {
+        if len > self.len {
+            let old = self.len;
+            self.len = len;
+            self.free(old..len);
+        }
+    }
+}
+
+// one vertex buffer and one index buffer shared by every mesh, so meshes can be drawn without
+// rebinding with `draw_indexed(mesh.index_range(), mesh.base_vertex(), ..)`.
+// indices are always u32 here, the u16 narrowing of `IndexBuffer::create` only applies to
+// standalone `VertexBuffer`s
+pub struct GeometryArena<V: Bufferable> {
+    device: Arc<Device>,
+    vertex_buffer: BufferContainer<V>,
+    index_buffer: BufferContainer<u32>,
+    vertices: FreeList,
+    indices: FreeList,
+}
+
+impl<V: Bufferable> GeometryArena<V> {
+    pub const MIN_VERTICES: u64 = 1024;
+    pub const MIN_INDICES: u64 = 4096;
+
+    pub fn new(device: Arc<Device>) -> Self {
+        assert!(
+            (std::mem::size_of::<V>() as BufferAddress) % wgpu::COPY_BUFFER_ALIGNMENT == 0,
+            "the vertex size has to be a multiple of 4 bytes"
+        );
+        Self {
+            vertex_buffer: BufferContainer::create_with_size(
+                Self::MIN_VERTICES,
+                &device,
+                BufferUsages::VERTEX | BufferUsages::COPY_DST | BufferUsages::COPY_SRC,
+                false,
+            ),
+            index_buffer: BufferContainer::create_with_size(
+                Self::MIN_INDICES,
+                &device,
+                BufferUsages::INDEX | BufferUsages::COPY_DST | BufferUsages::COPY_SRC,
+                false,
+            ),
+            vertices: FreeList::new(Self::MIN_VERTICES),
+            indices: FreeList::new(Self::MIN_INDICES),
+            device,
+        }
+    }
+
+    // the buffers grow when there is no free range big enough for the mesh, the growth and the
+    // mesh data are both recorded in `uploader`
+    pub fn insert(&mut self, mesh: &MeshData<V>, uploader: &mut Uploader) -> MeshRange {
+        let vertices = allocate(
+            &mut self.vertices,
+            &mut self.vertex_buffer,
+            mesh.vertices.len() as u64,
+            &self.device,
+            uploader,
+            "geometry vertices",
+        );
+        let indices = allocate(
+            &mut self.indices,
+            &mut self.index_buffer,
+            mesh.indices.len() as u64,
+            &self.device,
+            uploader,
+            "geometry indices",
+        );
+        uploader.write(&self.vertex_buffer, vertices.clone(), &mesh.vertices);
+        uploader.write(&self.index_buffer, indices.clone(), &mesh.indices);
+        MeshRange { vertices, indices }
+    }
+
+    pub fn remove(&mut self, mesh: MeshRange) {
+        self.vertices.free(mesh.vertices);
+        self.indices.free(mesh.indices);
+    }
+
+    pub fn vertex_buffer(&self) -> &BufferContainer<V> {
+        &self.vertex_buffer
+    }
+
+    pub fn index_buffer(&self) -> &BufferContainer<u32> {
+        &self.index_buffer
+    }
+
+    pub fn apply_to_pass<'a>(&'a self, pass: &mut RenderPass<'a>, slot: u32) {
+        pass.set_vertex_buffer(slot, self.vertex_buffer.buffer().slice(..));
+        pass.set_index_buffer(self.index_buffer.buffer().slice(..), u32::FORMAT);
+    }
+}
+
+fn allocate<T: Pod + Zeroable>(
+    list: &mut FreeList,
+    buffer: &mut BufferContainer<T>,
+    len: u64,
+    device: &Device,
+    uploader: &mut Uploader,
+    label: &str,
+) -> Range<u64> {
+    loop {
+        if let Some(range) = list.allocate(len) {
+            return range;
+        }
+        let new_len = (list.len() * 2).max(list.len() + len);
+        *buffer = buffer.clone_data_with_size(device, uploader.encoder(), new_len, label);
+        list.grow(new_len);
+    }
+}
+
+#[test]
+fn test_free_list() {
+    let mut list = FreeList::new(100);
+    let a = list.allocate(10).unwrap();
+    let b = list.allocate(20).unwrap();
+    let c = list.allocate(30).unwrap();
+    assert_eq!((a.clone(), b.clone(), c.clone()), (0..10, 10..30, 30..60));
+    assert_eq!(list.allocate(50), None);
+
+    // freed ranges are reused first fit
+    list.free(b);
+    assert_eq!(list.allocate(5), Some(10..15));
+    list.free(10..15);
+
+    // freeing the neighbours merges everything back into one range
+    list.free(a);
+    list.free(c);
+    assert_eq!(list.free_len(), 100);
+    assert_eq!(list.allocate(100), Some(0..100));
+
+    list.grow(150);
+    assert_eq!(list.allocate(50), Some(100..150));
+    assert_eq!(list.allocate(1), None);
+
+    let mesh = MeshRange {
+        vertices: 100..104,
+        indices: 300..306,
+    };
+    assert_eq!(
+        (mesh.base_vertex(), mesh.first_index(), mesh.count()),
+        (100, 300, 6)
+    );
+    assert_eq!(mesh.index_range(), 300..306);
+}
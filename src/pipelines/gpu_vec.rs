@@ -0,0 +1,170 @@
+use std::ops::Range;
+
+use bytemuck::{Pod, Zeroable};
+use wgpu::{BufferAddress, BufferUsages, Device, COPY_BUFFER_ALIGNMENT};
+
+use super::{buffers::BufferContainer, uploader::Uploader};
+
+// a `Vec` mirrored in a gpu buffer. `len` is the number of elements in use and `capacity` the
+// number the buffer can hold, only the changed part of `0..len` is written on `upload`
+pub struct GpuVec<T: Pod + Zeroable> {
+    data: Vec<T>,
+    buffer: BufferContainer<T>,
+    usage: BufferUsages,
+    // indices changed since the last upload
+    dirty: Vec<usize>,
+}
+
+impl<T: Pod + Zeroable> GpuVec<T> {
+    pub const MIN_CAPACITY: usize = 16;
+
+    // `COPY_DST` is always added to `usage`
+    pub fn new(device: &Device, usage: BufferUsages) -> Self {
+        Self::with_capacity(Self::MIN_CAPACITY, device, usage)
+    }
+
+    pub fn with_capacity(capacity: usize, device: &Device, usage: BufferUsages) -> Self {
+        // partial uploads start at `index * size_of::<T>()`, which has to stay 4 byte aligned
+        assert!(
+            (std::mem::size_of::<T>() as BufferAddress) % COPY_BUFFER_ALIGNMENT == 0,
+            "the element size has to be a multiple of 4 bytes"
+        );
+        let usage = usage | BufferUsages::COPY_DST;
+        let capacity = capacity.max(Self::MIN_CAPACITY);
+        Self {
+            data: Vec::with_capacity(capacity),
+            buffer: BufferContainer::create_with_size(capacity as u64, device, usage, false),
+            usage,
+            dirty: Vec::new(),
+        }
+    }
+
+    pub fn buffer(&self) -> &BufferContainer<T> {
+        &self.buffer
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    // the number of elements the gpu buffer holds
+    pub fn capacity(&self) -> usize {
+        self.buffer.size() as usize
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        &self.data
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.data.get(index)
+    }
+
+    pub fn push(&mut self, value: T) {
+        self.dirty.push(self.data.len());
+        self.data.push(value);
+    }
+
+    pub fn set(&mut self, index: usize, value: T) {
+        self.data[index] = value;
+        self.dirty.push(index);
+    }
+
+    // moves the last element into `index`, like `Vec::swap_remove`
+    pub fn swap_remove(&mut self, index: usize) -> T {
+        let value = self.data.swap_remove(index);
+        if index < self.data.len() {
+            self.dirty.push(index);
+        }
+        value
+    }
+
+    pub fn clear(&mut self) {
+        self.data.clear();
+        self.dirty.clear();
+    }
+
+    // writes the changed elements, returns true if the buffer was reallocated
+    pub fn upload(&mut self, uploader: &mut Uploader) -> bool {
+        if let Some(capacity) = resized_capacity(self.capacity(), self.len(), Self::MIN_CAPACITY) {
+            self.buffer = BufferContainer::create_with_size(
+                capacity as u64,
+                uploader.device(),
+                self.usage,
+                false,
+            );
+            uploader.write(&self.buffer, 0..self.data.len() as u64, &self.data);
+            self.dirty.clear();
+            return true;
+        }
+
+        for run in dirty_runs(&mut self.dirty, self.data.len()) {
+            uploader.write(
+                &self.buffer,
+                run.start as u64..run.end as u64,
+                &self.data[run],
+            );
+        }
+        self.dirty.clear();
+        false
+    }
+}
+
+// merges the dirty indices below `len` into sorted runs of consecutive indices
+fn dirty_runs(dirty: &mut Vec<usize>, len: usize) -> Vec<Range<usize>> {
+    dirty.sort_unstable();
+    dirty.dedup();
+    let mut ans = Vec::new();
+    let mut indices = dirty.iter().filter(|i| **i < len).peekable();
+    while let Some(&start) = indices.next() {
+        let mut end = start + 1;
+        while indices.next_if(|x| **x == end).is_some() {
+            end += 1;
+        }
+        ans.push(start..end);
+    }
+    ans
+}
+
+// grows to at least double the capacity, and only halves once `len` is down to a quarter of it
+// so a length that moves around a power of two doesn't reallocate every frame
+fn resized_capacity(capacity: usize, len: usize, min: usize) -> Option<usize> {
+    if len > capacity {
+        Some(len.max(capacity * 2).max(min))
+    } else if capacity > min && len <= capacity / 4 {
+        Some((capacity / 2).max(min))
+    } else {
+        None
+    }
+}
+
+#[test]
+fn test_resized_capacity() {
+    assert_eq!(resized_capacity(16, 17, 16), Some(32));
+    assert_eq!(resized_capacity(16, 100, 16), Some(100));
+    assert_eq!(resized_capacity(16, 16, 16), None);
+    assert_eq!(resized_capacity(16, 0, 16), None);
+
+    // shrinking needs the length to fall to a quarter, and then halves
+    assert_eq!(resized_capacity(64, 17, 16), None);
+    assert_eq!(resized_capacity(64, 16, 16), Some(32));
+    assert_eq!(resized_capacity(32, 9, 16), None);
+    // growing right after a shrink doesn't shrink back
+    assert_eq!(resized_capacity(32, 33, 16), Some(64));
+    assert_eq!(resized_capacity(64, 33, 16), None);
+}
+
+#[test]
+fn test_dirty_runs() {
+    let mut dirty = vec![7, 3, 4, 3, 0, 5, 9, 12];
+    assert_eq!(dirty_runs(&mut dirty, 10), [0..1, 3..6, 7..8, 9..10]);
+
+    // indices past the length were removed by `swap_remove`
+    let mut dirty = vec![5, 3, 0, 4];
+    assert_eq!(dirty_runs(&mut dirty, 4), [0..1, 3..4]);
+    assert!(dirty_runs(&mut Vec::new(), 4).is_empty());
+}
@@ -5,7 +5,12 @@ use super::bind_groups::{BindData, BindGroupContainer, BindGroupLayoutContainer}
 pub trait BindGroupCluster {
     type Args<'a>;
     type Groups: BindGroupLayoutContainerCluster;
-    fn apply_to_pipeline<'a>(p: &mut wgpu::RenderPass<'a>, args: Self::Args<'a>);
+    // `offsets[i]` are the dynamic offsets of group `i`, groups past the end of `offsets` get none
+    fn apply_to_pipeline<'a>(
+        p: &mut wgpu::RenderPass<'a>,
+        args: Self::Args<'a>,
+        offsets: &[&[u32]],
+    );
 
     fn create_bind_group_layouts(label: &str, device: &Device) -> Self::Groups;
 }
@@ -19,9 +24,9 @@ macro_rules! impl_cluster_group {
             type Groups = ($(BindGroupLayoutContainer<$t>),*);
 
             #[allow(non_snake_case,unused_assignments)]
-            fn apply_to_pipeline<'a>(p: &mut wgpu::RenderPass<'a>, ($($t),+): Self::Args<'a>) {
+            fn apply_to_pipeline<'a>(p: &mut wgpu::RenderPass<'a>, ($($t),+): Self::Args<'a>, offsets: &[&[u32]]) {
                 let mut i = 0;
-                {$(p.set_bind_group(i,$t.group(),&[]);i+=1;)*}
+                {$(p.set_bind_group(i,$t.group(),offsets.get(i as usize).copied().unwrap_or(&[]));i+=1;)*}
             }
             fn create_bind_group_layouts(label:&str,device:&Device) -> Self::Groups {
                 Self::Groups::create_layouts(label,device)
@@ -0,0 +1,446 @@
+use std::collections::HashMap;
+
+use matrix_engine::{
+    components::{
+        component::{Component, ComponentCollection},
+        resources::{Resource, ResourceHolder},
+    },
+    dispatchers::{
+        context::{Context, ResourceHolderManager},
+        dispatcher::{DispatchedData, ReadStorage, WriteStorage},
+        systems::AsyncSystem,
+    },
+    entity::Entity,
+};
+
+use crate::math::matrices::{Matrix4, Vector3};
+
+use super::transform::{
+    next_generation, relative_position, InstanceTransform, Transform, WorldPosition,
+};
+
+pub struct Parent(Entity);
+
+impl Component for Parent {}
+
+impl Parent {
+    pub fn new(entity: Entity) -> Self {
+        Self(entity)
+    }
+
+    pub fn entity(&self) -> &Entity {
+        &self.0
+    }
+}
+
+#[derive(Default)]
+pub struct Children(Vec<Entity>);
+
+impl Component for Children {}
+
+impl Children {
+    pub fn iter(&self) -> impl Iterator<Item = &Entity> {
+        self.0.iter()
+    }
+
+    pub fn contains(&self, entity: &Entity) -> bool {
+        self.0.contains(entity)
+    }
+
+    fn push(&mut self, entity: Entity) {
+        if !self.contains(&entity) {
+            self.0.push(entity);
+        }
+    }
+
+    fn remove(&mut self, entity: &Entity) {
+        self.0.retain(|x| x != entity);
+    }
+}
+
+// the world matrix, written by `TransformPropagationSystem`
+pub struct GlobalTransform {
+    matrix: Matrix4<f32>,
+    // the world matrix without the translation of the root's `WorldPosition`
+    relative: Matrix4<f32>,
+    // that translation, kept in f64 for `relative_to`
+    offset: Option<Vector3<f64>>,
+    // only bumped when the matrix actually changes
+    generation: u64,
+}
+
+impl Component for GlobalTransform {}
+
+impl GlobalTransform {
+    fn new(relative: Matrix4<f32>, offset: Option<Vector3<f64>>) -> Self {
+        Self {
+            matrix: with_offset(&relative, offset.as_ref()),
+            relative,
+            offset,
+            generation: next_generation(),
+        }
+    }
+
+    pub fn matrix(&self) -> &Matrix4<f32> {
+        &self.matrix
+    }
+
+    pub fn offset(&self) -> Option<&Vector3<f64>> {
+        self.offset.as_ref()
+    }
+
+    // the world matrix translated by `-origin`, the big values are subtracted in f64
+    pub fn relative_to(&self, origin: &Vector3<f64>) -> Matrix4<f32> {
+        let mut m = self.relative.clone();
+        let (base, position) = match &self.offset {
+            Some(offset) => (
+                [m[(3, 0)], m[(3, 1)], m[(3, 2)]],
+                relative_position(offset, origin),
+            ),
+            None => (
+                [0.0; 3],
+                relative_position(
+                    &[[m[(3, 0)] as f64, m[(3, 1)] as f64, m[(3, 2)] as f64]].into(),
+                    origin,
+                ),
+            ),
+        };
+        for i in 0..3 {
+            m[(3, i)] = base[i] + position[i];
+        }
+        m
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    pub fn position(&self) -> Vector3<f32> {
+        let m = &self.matrix;
+        [[m[(3, 0)], m[(3, 1)], m[(3, 2)]]].into()
+    }
+
+    fn update(&mut self, relative: Matrix4<f32>, offset: Option<Vector3<f64>>) {
+        let changed = self.relative.as_arrays() != relative.as_arrays()
+            || self.offset.as_ref().map(|x| x.as_arrays())
+                != offset.as_ref().map(|x| x.as_arrays());
+        if changed {
+            *self = Self::new(relative, offset);
+        }
+    }
+}
+
+fn with_offset(m: &Matrix4<f32>, offset: Option<&Vector3<f64>>) -> Matrix4<f32> {
+    let mut m = m.clone();
+    if let Some(offset) = offset {
+        for i in 0..3 {
+            m[(3, i)] += offset[i] as f32;
+        }
+    }
+    m
+}
+
+impl From<&GlobalTransform> for InstanceTransform {
+    fn from(value: &GlobalTransform) -> Self {
+        value.matrix.clone().into()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HierarchyError {
+    Cycle(Entity),
+    SingularParent(Entity),
+}
+
+// entities that are part of a cycle are treated as roots
+pub fn compute_world_matrices<'a>(
+    entities: impl IntoIterator<Item = &'a Entity>,
+    local: impl Fn(&Entity) -> Option<Matrix4<f32>>,
+    parent: impl Fn(&Entity) -> Option<Entity>,
+) -> (HashMap<Entity, Matrix4<f32>>, Vec<HierarchyError>) {
+    let mut worlds = HashMap::new();
+    let mut errors = Vec::new();
+
+    for entity in entities {
+        if worlds.contains_key(entity) {
+            continue;
+        }
+
+        // walk up until a known world matrix or a root
+        let mut chain = vec![*entity];
+        let mut base = None;
+        while let Some(p) = parent(chain.last().unwrap()) {
+            if let Some(world) = worlds.get(&p) {
+                base = Some(Matrix4::clone(world));
+                break;
+            }
+            if chain.contains(&p) {
+                errors.push(HierarchyError::Cycle(p));
+                break;
+            }
+            if local(&p).is_none() {
+                break;
+            }
+            chain.push(p);
+        }
+
+        let mut world = base.unwrap_or_else(Matrix4::identity);
+        for e in chain.into_iter().rev() {
+            if let Some(local) = local(&e) {
+                world = world.mul_matrix4(&local);
+            }
+            worlds.insert(e, world.clone());
+        }
+    }
+
+    (worlds, errors)
+}
+
+// the topmost ancestor, stops at a cycle
+pub fn root_of(entity: &Entity, parent: impl Fn(&Entity) -> Option<Entity>) -> Entity {
+    let mut visited = vec![*entity];
+    let mut current = *entity;
+    while let Some(p) = parent(&current) {
+        if visited.contains(&p) {
+            break;
+        }
+        visited.push(p);
+        current = p;
+    }
+    current
+}
+
+pub fn is_ancestor(
+    ancestor: &Entity,
+    entity: &Entity,
+    parent: impl Fn(&Entity) -> Option<Entity>,
+) -> bool {
+    let mut visited = vec![*entity];
+    let mut current = *entity;
+    while let Some(p) = parent(&current) {
+        if &p == ancestor {
+            return true;
+        }
+        if visited.contains(&p) {
+            return false;
+        }
+        visited.push(p);
+        current = p;
+    }
+    false
+}
+
+// the local matrix that keeps `world` under a parent at `parent_world`
+pub fn relative_matrix(world: &Matrix4<f32>, parent_world: &Matrix4<f32>) -> Option<Matrix4<f32>> {
+    Some(parent_world.inverse()?.mul_matrix4(world))
+}
+
+// roots with a `WorldPosition` are placed at the origin and their translation is kept in f64
+// in the `GlobalTransform` of every entity under them
+pub fn propagate_transforms(
+    transforms: &ComponentCollection<Transform>,
+    parents: &ComponentCollection<Parent>,
+    world_positions: &ComponentCollection<WorldPosition>,
+    globals: &mut ComponentCollection<GlobalTransform>,
+) -> Vec<HierarchyError> {
+    let world_position = |e: &Entity| world_positions.get(e).filter(|_| parents.get(e).is_none());
+    let (worlds, errors) = compute_world_matrices(
+        transforms.iter().map(|(e, _)| e),
+        |e| {
+            transforms.get(e).map(|t| match world_position(e) {
+                Some(_) => t.to_matrix_at(&Vector3::zeros()),
+                None => t.matrix().clone(),
+            })
+        },
+        |e| parents.get(e).map(|p| p.0),
+    );
+    let stale = globals
+        .iter()
+        .filter(|(e, _)| !worlds.contains_key(e))
+        .map(|(e, _)| *e)
+        .collect::<Vec<_>>();
+    for e in stale {
+        globals.remove(&e);
+    }
+    for (e, world) in worlds {
+        // the same walk as `compute_world_matrices`, a parent without a transform ends it
+        let root = root_of(&e, |e| {
+            parents
+                .get(e)
+                .map(|p| p.0)
+                .filter(|p| transforms.get(p).is_some())
+        });
+        let offset = world_position(&root)
+            .zip(transforms.get(&root))
+            .map(|(position, t)| position.translation_for(t));
+        match globals.get_mut(&e) {
+            Some(global) => global.update(world, offset),
+            None => globals.insert(e, GlobalTransform::new(world, offset)),
+        }
+    }
+    errors
+}
+
+// moves `entity` under `new_parent` (or to the root) without changing its world pose
+pub fn set_parent(
+    entity: Entity,
+    new_parent: Option<Entity>,
+    transforms: &mut ComponentCollection<Transform>,
+    parents: &mut ComponentCollection<Parent>,
+    children: &mut ComponentCollection<Children>,
+) -> Result<(), HierarchyError> {
+    if let Some(new_parent) = new_parent {
+        if new_parent == entity
+            || is_ancestor(&entity, &new_parent, |e| parents.get(e).map(|p| p.0))
+        {
+            return Err(HierarchyError::Cycle(entity));
+        }
+    }
+
+    let world_of = |e: &Entity| {
+        let (worlds, _) = compute_world_matrices(
+            [e],
+            |e| transforms.get(e).map(|t| t.matrix().clone()),
+            |e| parents.get(e).map(|p| p.0),
+        );
+        worlds.get(e).cloned().unwrap_or_else(Matrix4::identity)
+    };
+
+    let world = world_of(&entity);
+    let local = match new_parent {
+        Some(p) => {
+            relative_matrix(&world, &world_of(&p)).ok_or(HierarchyError::SingularParent(p))?
+        }
+        None => world,
+    };
+
+    if let Some(old) = parents.remove(&entity) {
+        if let Some(c) = children.get_mut(&old.0) {
+            c.remove(&entity);
+        }
+    }
+    if let Some(p) = new_parent {
+        parents.insert(entity, Parent(p));
+        match children.get_mut(&p) {
+            Some(c) => c.push(entity),
+            None => children.insert(p, Children(vec![entity])),
+        }
+    }
+    if let Some(t) = transforms.get_mut(&entity) {
+        t.set_matrix(&local);
+    }
+
+    Ok(())
+}
+
+// the errors of the last propagation, replaced every run
+#[derive(Default)]
+pub struct HierarchyErrors(pub Vec<HierarchyError>);
+
+impl Resource for HierarchyErrors {}
+
+// run before `RendererSystem` so it draws the propagated `GlobalTransform`s
+pub struct TransformPropagationSystem;
+
+impl AsyncSystem for TransformPropagationSystem {
+    type Query = (
+        ReadStorage<ComponentCollection<Transform>>,
+        ReadStorage<ComponentCollection<Parent>>,
+        ReadStorage<ComponentCollection<WorldPosition>>,
+        WriteStorage<ComponentCollection<GlobalTransform>>,
+        WriteStorage<ResourceHolder<HierarchyErrors>>,
+    );
+
+    fn run(
+        &mut self,
+        ctx: &Context,
+        (transforms, parents, world_positions, globals, errors): &mut Self::Query,
+    ) {
+        let errors = ctx.get_or_insert_resource_with(errors.holder_mut(), HierarchyErrors::default);
+        errors.0 = propagate_transforms(
+            transforms.get(),
+            parents.get(),
+            world_positions.get(),
+            globals.get(),
+        );
+    }
+}
+
+#[test]
+fn test_hierarchy() {
+    let translate = |x: f32, y: f32, z: f32| {
+        Transform::identity()
+            .with_position([[x, y, z]].into())
+            .to_matrix()
+    };
+
+    let (root, child, grandchild, lonely) = (
+        Entity::default(),
+        Entity::default(),
+        Entity::default(),
+        Entity::default(),
+    );
+    let mut locals = HashMap::new();
+    locals.insert(root, translate(1.0, 0.0, 0.0));
+    locals.insert(child, translate(0.0, 2.0, 0.0));
+    locals.insert(grandchild, translate(0.0, 0.0, 3.0));
+    locals.insert(lonely, translate(5.0, 5.0, 5.0));
+    let mut parents = HashMap::new();
+    parents.insert(child, root);
+    parents.insert(grandchild, child);
+
+    let (worlds, errors) = compute_world_matrices(
+        [grandchild, lonely, root, child].iter(),
+        |e| locals.get(e).cloned(),
+        |e| parents.get(e).cloned(),
+    );
+    assert!(errors.is_empty());
+    let world = &worlds[&grandchild];
+    assert_eq!(
+        [world[(3, 0)], world[(3, 1)], world[(3, 2)]],
+        [1.0, 2.0, 3.0]
+    );
+    assert_eq!(worlds[&lonely][(3, 0)], 5.0);
+
+    assert_eq!(root_of(&grandchild, |e| parents.get(e).cloned()), root);
+    assert!(is_ancestor(&root, &grandchild, |e| parents.get(e).cloned()));
+    assert!(!is_ancestor(&grandchild, &root, |e| parents
+        .get(e)
+        .cloned()));
+
+    // keeping the world pose while moving grandchild under lonely
+    let local = relative_matrix(&worlds[&grandchild], &worlds[&lonely]).unwrap();
+    let moved = worlds[&lonely].mul_matrix(&local);
+    for (pos, x) in moved.iter() {
+        assert!((x - world[pos]).abs() < 1e-5);
+    }
+
+    parents.insert(root, grandchild);
+    let (worlds, errors) = compute_world_matrices(
+        [root, child, grandchild].iter(),
+        |e| locals.get(e).cloned(),
+        |e| parents.get(e).cloned(),
+    );
+    assert_eq!(errors.len(), 1);
+    assert_eq!(worlds.len(), 3);
+}
+
+#[test]
+fn test_world_position_children() {
+    let root = Transform::identity().with_scale([[2.0, 2.0, 2.0]].into());
+    let child = Transform::identity().with_position([[0.125, 0.0, 0.0]].into());
+    let position = WorldPosition([[10_000_000.0, 0.0, -5_000_000.0]].into());
+
+    // what `propagate_transforms` stores for the child of a root with a `WorldPosition`
+    let relative = root
+        .to_matrix_at(&Vector3::zeros())
+        .mul_matrix4(child.matrix());
+    let global = GlobalTransform::new(relative, Some(position.translation_for(&root)));
+
+    // the child keeps its small offset from the root, which f32 would round away
+    let origin: Vector3<f64> = [[10_000_000.0, 1.0, -5_000_000.0]].into();
+    let m = global.relative_to(&origin);
+    assert_eq!([m[(3, 0)], m[(3, 1)], m[(3, 2)]], [0.25, -1.0, 0.0]);
+    assert_eq!(m[(0, 0)], 2.0);
+    assert_eq!(global.matrix()[(3, 0)], 10_000_000.0);
+}
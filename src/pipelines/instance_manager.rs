@@ -4,36 +4,152 @@ use std::{
     sync::Arc,
 };
 
+use bytemuck::{Pod, Zeroable};
+use matrix_engine::{components::component::Component, entity::Entity};
 use wgpu::{BufferUsages, Device, Queue};
 
 use crate::renderer::render_object::RenderObject;
 
 use super::{
     bind_groups::BindGroupContainer,
-    buffers::{BufferContainer, Bufferable, Vertex, VertexBuffer},
+    buffers::{BufferContainer, Bufferable, Vertex},
+    geometry_arena::{GeometryArena, MeshData, MeshRange},
+    gpu_vec::GpuVec,
     group_layout_manager::BindGroupLayoutManager,
     texture::MatrixTexture,
-    transform::{InstanceTransform, Transform},
+    transform::{next_generation, InstanceTransform},
+    uploader::Uploader,
 };
 
 pub trait VertexStructure<Vertex: Bufferable>: Any {
-    fn craete_buffer(&self, device: &Device, queue: &Queue) -> VertexBuffer<Vertex>;
+    fn mesh_data(&self) -> MeshData<Vertex>;
 }
 
-pub struct InstancedData {
+// the main shader's per-instance inputs besides the model matrix
+#[derive(Clone, Debug)]
+pub struct InstanceAttributes {
+    tint: [f32; 4],
+    uv_offset: [f32; 2],
+    uv_scale: [f32; 2],
+    // bumped by every setter, like `Transform::generation`
+    generation: u64,
+}
+
+impl Component for InstanceAttributes {}
+
+impl Default for InstanceAttributes {
+    fn default() -> Self {
+        Self {
+            tint: [1.0; 4],
+            uv_offset: [0.0; 2],
+            uv_scale: [1.0; 2],
+            generation: next_generation(),
+        }
+    }
+}
+
+impl InstanceAttributes {
+    pub fn with_tint(mut self, tint: [f32; 4]) -> Self {
+        self.set_tint(tint);
+        self
+    }
+    pub fn with_uv_rect(mut self, offset: [f32; 2], scale: [f32; 2]) -> Self {
+        self.set_uv_rect(offset, scale);
+        self
+    }
+
+    pub fn tint(&self) -> [f32; 4] {
+        self.tint
+    }
+    pub fn uv_offset(&self) -> [f32; 2] {
+        self.uv_offset
+    }
+    pub fn uv_scale(&self) -> [f32; 2] {
+        self.uv_scale
+    }
+
+    pub fn set_tint(&mut self, tint: [f32; 4]) {
+        self.tint = tint;
+        self.generation = next_generation();
+    }
+    pub fn set_uv_rect(&mut self, offset: [f32; 2], scale: [f32; 2]) {
+        self.uv_offset = offset;
+        self.uv_scale = scale;
+        self.generation = next_generation();
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+}
+
+// the generations an instance was built from, 0 stands for a missing part since generations
+// start at 1
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct InstanceGeneration {
+    // of the `GlobalTransform`, or of the `Transform` without one
+    pub transform: u64,
+    pub attributes: u64,
+    // of the camera's render origin in camera relative mode
+    pub origin: u64,
+}
+
+// the model matrix keeps the locations of `InstanceTransform`
+#[repr(C)]
+#[derive(Pod, Zeroable, Clone, Copy, Bufferable)]
+#[step_mode(instance)]
+pub struct MainInstance {
+    #[location(5)]
+    transform: [[f32; 4]; 4],
+    #[location(9)]
+    tint: [f32; 4],
+    // xy is the offset and zw the scale
+    #[location(10)]
+    uv_rect: [f32; 4],
+}
+
+impl MainInstance {
+    pub fn new(transform: InstanceTransform, attributes: &InstanceAttributes) -> Self {
+        let [x, y] = attributes.uv_offset();
+        let [w, h] = attributes.uv_scale();
+        Self {
+            transform: bytemuck::cast(transform),
+            tint: attributes.tint(),
+            uv_rect: [x, y, w, h],
+        }
+    }
+}
+
+impl From<InstanceTransform> for MainInstance {
+    fn from(value: InstanceTransform) -> Self {
+        Self::new(value, &InstanceAttributes::default())
+    }
+}
+
+// `I` is the per-instance vertex data, its `Bufferable::describe` has to use the instance step
+// mode and locations that do not overlap the structure's vertex attributes
+pub struct InstancedData<I: Bufferable = InstanceTransform> {
     texture: MatrixTexture,
     texture_group: BindGroupContainer<(MatrixTexture,)>,
-    transform_buffer: BufferContainer<InstanceTransform>,
-    transform_vec: Vec<InstanceTransform>,
-    buffer: Arc<VertexBuffer<Vertex>>,
+    instances: GpuVec<I>,
+    // the entity that owns every slot of `instances`
+    entities: Vec<Entity>,
+    slots: HashMap<Entity, InstanceSlot>,
+    mesh: MeshRange,
 }
 
-impl InstancedData {
+struct InstanceSlot {
+    index: usize,
+    generation: Option<InstanceGeneration>,
+    seen: bool,
+}
+
+impl<I: Bufferable> InstancedData<I> {
     pub fn new(
         texture_name: &str,
         device: &Device,
         queue: &Queue,
-        buffer: Arc<VertexBuffer<Vertex>>,
+        mesh: MeshRange,
         manager: &mut BindGroupLayoutManager,
     ) -> Self {
         let t =
@@ -42,147 +158,206 @@ impl InstancedData {
         let group = manager.create_group::<(MatrixTexture,)>((&t,));
         Self {
             texture: t,
-            buffer,
-            transform_vec: Vec::new(),
+            mesh,
+            instances: GpuVec::new(device, BufferUsages::VERTEX),
+            entities: Vec::new(),
+            slots: HashMap::new(),
             texture_group: group,
-            transform_buffer: BufferContainer::create_buffer(
-                &InstanceTransform::default(),
-                device,
-                queue,
-                BufferUsages::VERTEX | BufferUsages::COPY_DST,
-                true,
-            ),
         }
     }
 
-    fn prepare_capacity(&mut self, _count: u64, device: &Device, queue: &Queue) -> bool {
-        // if self.transform_buffer.size() < count || (self.transform_buffer.size() / 2) > count {
-        //     let new_size = (2_u32).pow((count as f32).log2().ceil() as u32);
-        //     self.transform_buffer = BufferContainer::create_with_size(
-        //         count,
-        //         device,
-        //         self.transform_buffer.usage(),
-        //         true,
-        //     );
-        //     true
-        // } else {
-        //     false
-        // }
-        if self.transform_buffer.size() as usize != self.transform_vec.capacity() {
-            self.transform_buffer = BufferContainer::create_with_size(
-                self.transform_vec.capacity() as u64,
-                device,
-                BufferUsages::COPY_DST | BufferUsages::VERTEX,
-                false,
-            );
-            queue.write_buffer(
-                self.transform_buffer.buffer(),
-                0,
-                bytemuck::cast_slice(&self.transform_vec),
-            );
-
-            println!("allocated! {}",self.transform_vec.capacity());
-            return true;
+    // drops the entities that were not set since the last `clear` and uploads the changed slots,
+    // returns true if the buffer was reallocated
+    fn prepare(&mut self, uploader: &mut Uploader) -> bool {
+        let removed = self
+            .slots
+            .iter()
+            .filter(|(_, slot)| !slot.seen)
+            .map(|(e, _)| *e)
+            .collect::<Vec<_>>();
+        for e in removed {
+            let Some(slot) = self.slots.remove(&e) else {
+                continue;
+            };
+            self.instances.swap_remove(slot.index);
+            self.entities.swap_remove(slot.index);
+            if let Some(moved) = self.entities.get(slot.index) {
+                if let Some(moved) = self.slots.get_mut(moved) {
+                    moved.index = slot.index;
+                }
+            }
         }
-        false
+        self.instances.upload(uploader)
     }
 
     pub fn texture_group(&self) -> &BindGroupContainer<(MatrixTexture,)> {
         &self.texture_group
     }
 
-    pub fn transform_buffer(&self) -> &BufferContainer<InstanceTransform> {
-        &self.transform_buffer
+    pub fn instance_buffer(&self) -> &BufferContainer<I> {
+        self.instances.buffer()
     }
 
-    pub fn structure_buffer(&self) -> &VertexBuffer<Vertex> {
-        self.buffer.as_ref()
+    // where the structure's mesh is in the manager's `GeometryArena`
+    pub fn mesh(&self) -> &MeshRange {
+        &self.mesh
     }
 
-    pub fn push(&mut self, raw: InstanceTransform, _device: &Device, _queue: &Queue) {
-        self.transform_vec.push(raw);
+    pub fn len(&self) -> usize {
+        self.instances.len()
+    }
 
-        if self.transform_vec.capacity() != self.transform_buffer.size() as usize {}
+    pub fn is_empty(&self) -> bool {
+        self.instances.is_empty()
+    }
+
+    // `instance` is only called when `generation` differs from the uploaded one,
+    // a `None` generation is always recomputed but only uploaded if its bytes changed
+    pub fn set(
+        &mut self,
+        entity: Entity,
+        generation: Option<InstanceGeneration>,
+        instance: impl FnOnce() -> I,
+    ) {
+        match self.slots.get_mut(&entity) {
+            Some(slot) => {
+                slot.seen = true;
+                if generation.is_some() && slot.generation == generation {
+                    return;
+                }
+                slot.generation = generation;
+                let instance = instance();
+                let old = &self.instances.as_slice()[slot.index];
+                if bytemuck::bytes_of(old) != bytemuck::bytes_of(&instance) {
+                    self.instances.set(slot.index, instance);
+                }
+            }
+            None => {
+                let index = self.instances.len();
+                self.instances.push(instance());
+                self.entities.push(entity);
+                self.slots.insert(
+                    entity,
+                    InstanceSlot {
+                        index,
+                        generation,
+                        seen: true,
+                    },
+                );
+            }
+        }
     }
 
     pub fn clear(&mut self) {
-        self.transform_vec.clear();
+        for slot in self.slots.values_mut() {
+            slot.seen = false;
+        }
     }
 }
 
-pub struct InstanceManager {
+pub struct InstanceManager<I: Bufferable = InstanceTransform> {
     device: Arc<Device>,
     queue: Arc<Queue>,
-    data: HashMap<(TypeId, String), (u64, InstancedData)>,
-    buffer: HashMap<TypeId, (u64, Arc<VertexBuffer<Vertex>>)>,
+    data: HashMap<(TypeId, String), InstancedData<I>>,
+    arena: GeometryArena<Vertex>,
+    meshes: HashMap<TypeId, MeshRange>,
 }
 
-impl InstanceManager {
+impl<I: Bufferable> InstanceManager<I> {
     pub fn new(device: Arc<Device>, queue: Arc<Queue>) -> Self {
         Self {
+            arena: GeometryArena::new(device.clone()),
             device,
             queue,
-            buffer: Default::default(),
+            meshes: Default::default(),
             data: Default::default(),
         }
     }
 
     pub fn registr_object(
         &mut self,
+        entity: Entity,
         obj: &RenderObject,
-        transform: &Transform,
+        generation: Option<InstanceGeneration>,
+        instance: impl FnOnce() -> I,
         group_manager: &mut BindGroupLayoutManager,
+        uploader: &mut Uploader,
     ) {
+        let mesh = self
+            .meshes
+            .entry(obj.structure_type_id())
+            .or_insert_with(|| self.arena.insert(&obj.mesh_data(), uploader));
         self.data
             .entry((obj.structure_type_id(), obj.texture_name().into()))
-            .and_modify(|(x, _)| *x += 1)
             .or_insert_with(|| {
-                (
-                    1,
-                    InstancedData::new(
-                        obj.texture_name(),
-                        &self.device,
-                        &self.queue,
-                        self.buffer
-                            .entry(obj.structure_type_id())
-                            .or_insert_with(|| {
-                                (1, Arc::new(obj.create_buffer(&self.device, &self.queue)))
-                            })
-                            .1
-                            .clone(),
-                        group_manager,
-                    ),
+                InstancedData::new(
+                    obj.texture_name(),
+                    &self.device,
+                    &self.queue,
+                    mesh.clone(),
+                    group_manager,
                 )
             })
-            .1
-            .push(
-                InstanceTransform::from(transform),
-                &self.device,
-                &self.queue,
-            );
-        self.buffer
-            .entry(obj.structure_type_id())
-            .and_modify(|(x, _)| *x += 1)
-            .or_insert_with(|| (1, Arc::new(obj.create_buffer(&self.device, &self.queue))));
+            .set(entity, generation, instance);
     }
-    pub fn prepare(&mut self) -> bool {
-        self.data.retain(|_, (x, _)| x > &mut 0);
-        self.data
-            .iter_mut()
-            .map(|((_structure, _texture_name), (count, data))| {
-                data.prepare_capacity(*count, &self.device, &self.queue)
-            })
-            .any(|x| x)
+
+    pub fn prepare(&mut self, uploader: &mut Uploader) -> bool {
+        let mut reallocated = false;
+        for data in self.data.values_mut() {
+            reallocated |= data.prepare(uploader);
+        }
+        self.data.retain(|_, data| !data.is_empty());
+
+        // frees the meshes of structures that no group draws anymore
+        let used = self.data.keys().map(|(id, _)| *id).collect::<Vec<_>>();
+        let arena = &mut self.arena;
+        self.meshes.retain(|id, mesh| {
+            let keep = used.contains(id);
+            if !keep {
+                arena.remove(mesh.clone());
+            }
+            keep
+        });
+        reallocated
     }
-    pub fn iter_data(&self) -> impl Iterator<Item = (&'_ InstancedData, u32)> {
-        self.data
-            .iter()
-            .map(|(_, (count, data))| (data, *count as u32))
+    pub fn arena(&self) -> &GeometryArena<Vertex> {
+        &self.arena
+    }
+    pub fn iter_data(&self) -> impl Iterator<Item = (&'_ InstancedData<I>, u32)> {
+        self.data.values().map(|data| (data, data.len() as u32))
     }
     pub fn clear(&mut self) {
-        for (_, (i, data)) in &mut self.data {
-            *i = 0;
+        for data in self.data.values_mut() {
             data.clear();
         }
     }
 }
+
+#[test]
+fn test_main_instance_layout() {
+    let layout = MainInstance::describe();
+    assert_eq!(layout.array_stride, 96);
+    assert_eq!(layout.step_mode, wgpu::VertexStepMode::Instance);
+    // the model matrix keeps the locations and offsets of `InstanceTransform`
+    for (attr, transform_attr) in layout
+        .attributes
+        .iter()
+        .zip(InstanceTransform::describe().attributes)
+    {
+        assert_eq!(attr.shader_location, transform_attr.shader_location);
+        assert_eq!(attr.offset, transform_attr.offset);
+    }
+    assert_eq!(layout.attributes[4].shader_location, 9);
+    assert_eq!(layout.attributes[5].offset, 80);
+
+    let mut attributes = InstanceAttributes::default();
+    let generation = attributes.generation();
+    attributes.set_uv_rect([0.5, 0.25], [0.5, 0.5]);
+    assert_ne!(attributes.generation(), generation);
+    let instance = MainInstance::new(InstanceTransform::default(), &attributes);
+    assert_eq!(instance.uv_rect, [0.5, 0.25, 0.5, 0.5]);
+    assert_eq!(
+        MainInstance::from(InstanceTransform::default()).tint,
+        [1.0; 4]
+    );
+}
@@ -28,12 +28,24 @@ pub struct MatrixRenderPipeline<B: BufferGroup, T: BindGroupCluster> {
     pipeline: RenderPipeline,
     layout: PipelineLayout,
     shaders: MatrixShaders,
+    depth_stencil: Option<DepthStencilState>,
 }
 impl<B: BufferGroup, T: BindGroupCluster> Resource for MatrixRenderPipeline<B, T> {}
 
 impl<B: BufferGroup, T: BindGroupCluster> MatrixRenderPipeline<B, T> {
+    // groups with a `DynamicUniform` need `apply_groups_with_offsets`, binding them without
+    // their dynamic offsets fails validation
     pub fn apply_groups<'a>(&self, pass: &mut RenderPass<'a>, data: T::Args<'a>) {
-        T::apply_to_pipeline(pass, data);
+        T::apply_to_pipeline(pass, data, &[]);
+    }
+    // `offsets[i]` are the dynamic offsets of the group at index `i`
+    pub fn apply_groups_with_offsets<'a>(
+        &self,
+        pass: &mut RenderPass<'a>,
+        data: T::Args<'a>,
+        offsets: &[&[u32]],
+    ) {
+        T::apply_to_pipeline(pass, data, offsets);
     }
     pub fn set_vertex_buffer<'a, Buff: Bufferable>(
         &self,
@@ -43,7 +55,7 @@ impl<B: BufferGroup, T: BindGroupCluster> MatrixRenderPipeline<B, T> {
     ) {
         pass.set_vertex_buffer(slot, buff.buffer().buffer().slice(..));
         if let Some(b) = buff.index_buffer() {
-            pass.set_index_buffer(b.buffer().slice(..), wgpu::IndexFormat::Uint16);
+            b.apply_to_pass(pass);
         }
     }
     pub fn set_buffer<'a, Buff: Bufferable>(
@@ -58,6 +70,10 @@ impl<B: BufferGroup, T: BindGroupCluster> MatrixRenderPipeline<B, T> {
         &self.pipeline
     }
 
+    pub fn depth_stencil(&self) -> Option<&DepthStencilState> {
+        self.depth_stencil.as_ref()
+    }
+
     pub fn begin<'a: 'b, 'b>(&'a self, pass: &mut RenderPass<'b>) {
         pass.set_pipeline(&self.pipeline)
     }
@@ -117,7 +133,7 @@ impl<B: BufferGroup, T: BindGroupCluster> MatrixRenderPipeline<B, T> {
                 })],
             }),
             primitive: primitive_state,
-            depth_stencil,
+            depth_stencil: depth_stencil.clone(),
             multisample: wgpu::MultisampleState {
                 count: 1,
                 mask: !0,
@@ -131,6 +147,7 @@ impl<B: BufferGroup, T: BindGroupCluster> MatrixRenderPipeline<B, T> {
             pipeline,
             shaders,
             layout,
+            depth_stencil,
         }
     }
 
@@ -138,8 +155,9 @@ impl<B: BufferGroup, T: BindGroupCluster> MatrixRenderPipeline<B, T> {
         &self,
         pass: &mut RenderPass<'_>,
         range: std::ops::Range<u32>,
+        base_vertex: i32,
         instances: std::ops::Range<u32>,
     ) {
-        pass.draw_indexed(range, 0, instances);
+        pass.draw_indexed(range, base_vertex, instances);
     }
 }
@@ -1,10 +1,15 @@
 pub mod matrix_render_pipeline;
 pub mod bind_groups;
+pub mod bind_buffers;
 pub mod texture;
 pub mod group_cluster;
 pub mod shaders;
 pub mod buffers;
+pub mod gpu_vec;
+pub mod geometry_arena;
+pub mod uploader;
 pub mod instance_manager;
 pub mod structures;
 pub mod transform;
+pub mod hierarchy;
 pub mod group_layout_manager;
\ No newline at end of file
@@ -1,30 +1,15 @@
-use wgpu::{BufferUsages, Device, Queue};
-
 use crate::pipelines::{
-    buffers::{BufferContainer, Vertex, VertexBuffer},
-    instance_manager::VertexStructure,
+    buffers::Vertex, geometry_arena::MeshData, instance_manager::VertexStructure,
 };
 
 pub struct Plain;
 
 impl VertexStructure<Vertex> for Plain {
-    fn craete_buffer(&self, device: &Device, queue: &Queue) -> VertexBuffer<Vertex> {
-        VertexBuffer::new(
-            BufferContainer::<Vertex>::create_buffer(
-                &Self::VERTICES,
-                device,
-                queue,
-                BufferUsages::COPY_DST | BufferUsages::VERTEX,
-                false,
-            ),
-            Some(BufferContainer::<u16>::create_buffer(
-                &Self::INDEXES,
-                device,
-                queue,
-                BufferUsages::INDEX | BufferUsages::COPY_DST,
-                false,
-            )),
-        )
+    fn mesh_data(&self) -> MeshData<Vertex> {
+        MeshData {
+            vertices: Self::VERTICES.to_vec(),
+            indices: Self::INDEXES.to_vec(),
+        }
     }
 }
 
@@ -47,5 +32,5 @@ impl Plain {
             texture_pos: [0.0, 1.0],
         },
     ];
-    const INDEXES: &[u16] = &[0, 2, 1, 0, 3, 2];
+    const INDEXES: &[u32] = &[0, 2, 1, 0, 3, 2];
 }
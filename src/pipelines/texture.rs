@@ -1,7 +1,12 @@
-use std::{fs, io};
+use std::{fs, io, num::NonZeroU32};
 
-use image::{GenericImageView, ImageError};
-use wgpu::TextureDescriptor;
+use image::{GenericImageView, ImageError, RgbaImage};
+use wgpu::{
+    BufferAsyncError, BufferDescriptor, BufferUsages, CommandEncoderDescriptor, TextureDescriptor,
+    TextureFormat, TextureUsages, COPY_BYTES_PER_ROW_ALIGNMENT,
+};
+
+use super::buffers::map_read;
 
 pub struct MatrixTexture {
     texture: wgpu::Texture,
@@ -15,6 +20,13 @@ pub enum MatrixTextureLoadError {
     IOError(io::Error),
 }
 
+#[derive(Debug)]
+pub enum MatrixTextureReadError {
+    // only 8 bit rgba and bgra textures can be read as rgba
+    UnsupportedFormat(TextureFormat),
+    MapError(BufferAsyncError),
+}
+
 impl MatrixTexture {
     pub fn from_name(
         img: &str,
@@ -55,7 +67,9 @@ impl MatrixTexture {
             sample_count: 1,
             dimension: wgpu::TextureDimension::D2,
             format: wgpu::TextureFormat::Rgba8UnormSrgb,
-            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_DST
+                | wgpu::TextureUsages::COPY_SRC,
             view_formats: &[],
         });
 
@@ -138,6 +152,63 @@ impl MatrixTexture {
         }
     }
 
+    // reads the first mip level of an 8 bit rgba or bgra texture, it needs `COPY_SRC`.
+    // like `map_read` it only resolves once the device was polled after the copy
+    pub async fn read_rgba(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> Result<RgbaImage, MatrixTextureReadError> {
+        let format = self.texture.format();
+        let bgra = match format {
+            TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb => false,
+            TextureFormat::Bgra8Unorm | TextureFormat::Bgra8UnormSrgb => true,
+            _ => return Err(MatrixTextureReadError::UnsupportedFormat(format)),
+        };
+        assert!(
+            self.texture.usage().contains(TextureUsages::COPY_SRC),
+            "a texture needs COPY_SRC to be read back"
+        );
+        let size = wgpu::Extent3d {
+            depth_or_array_layers: 1,
+            ..self.texture.size()
+        };
+        let padded_row = padded_bytes_per_row(size.width);
+
+        let staging = device.create_buffer(&BufferDescriptor {
+            label: Some("texture readback"),
+            size: (padded_row * size.height) as u64,
+            usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("texture readback"),
+        });
+        encoder.copy_texture_to_buffer(
+            self.texture.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &staging,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: NonZeroU32::new(padded_row),
+                    rows_per_image: NonZeroU32::new(size.height),
+                },
+            },
+            size,
+        );
+        queue.submit(std::iter::once(encoder.finish()));
+
+        let data = map_read(&staging, ..)
+            .await
+            .map_err(MatrixTextureReadError::MapError)?;
+        let mut pixels = unpad_rows(&data, size.width * 4, padded_row);
+        if bgra {
+            pixels.chunks_exact_mut(4).for_each(|x| x.swap(0, 2));
+        }
+        Ok(RgbaImage::from_raw(size.width, size.height, pixels)
+            .expect("the readback has a pixel for every texel"))
+    }
+
     pub fn view(&self) -> &wgpu::TextureView {
         &self.view
     }
@@ -146,6 +217,19 @@ impl MatrixTexture {
     }
 }
 
+// rows of a texture copy have to start at multiples of `COPY_BYTES_PER_ROW_ALIGNMENT`
+fn padded_bytes_per_row(width: u32) -> u32 {
+    let row = width * 4;
+    row.div_ceil(COPY_BYTES_PER_ROW_ALIGNMENT) * COPY_BYTES_PER_ROW_ALIGNMENT
+}
+
+fn unpad_rows(data: &[u8], row: u32, padded_row: u32) -> Vec<u8> {
+    data.chunks(padded_row as usize)
+        .flat_map(|x| &x[..row as usize])
+        .copied()
+        .collect()
+}
+
 #[macro_export]
 macro_rules! texture {
     ($path:expr,$device:expr,$queue:expr,$label:expr) => {
@@ -157,3 +241,15 @@ macro_rules! texture {
         )
     };
 }
+
+#[test]
+fn test_readback_rows() {
+    assert_eq!(padded_bytes_per_row(1), 256);
+    assert_eq!(padded_bytes_per_row(64), 256);
+    assert_eq!(padded_bytes_per_row(65), 512);
+
+    let mut data = vec![0; 512];
+    data[..8].copy_from_slice(&[1, 2, 3, 4, 5, 6, 7, 8]);
+    data[256..264].copy_from_slice(&[9, 10, 11, 12, 13, 14, 15, 16]);
+    assert_eq!(unpad_rows(&data, 8, 256), (1..=16).collect::<Vec<u8>>());
+}
@@ -1,69 +1,420 @@
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    OnceLock,
+};
+
 use bytemuck::{Pod, Zeroable};
-use matrix_engine::components::component::Component;
-use wgpu::{BufferAddress, VertexAttribute};
+use matrix_engine::{
+    components::{
+        component::{Component, ComponentCollection},
+        resources::ResourceHolder,
+    },
+    dispatchers::{
+        context::Context,
+        dispatcher::{DispatchedData, ReadStorage, WriteStorage},
+        systems::AsyncSystem,
+    },
+};
 
-use crate::math::{
-    matrices::{Matrix4, Vector3},
-    vectors::Vector3D,
+use crate::{
+    math::{
+        matrices::{Matrix4, Vector3, Vector4},
+        quaternions::Quaternion,
+        vectors::Vector3D,
+    },
+    renderer::camera::CameraResource,
 };
 
 use super::buffers::Bufferable;
 
+// the order of the matrix product, `TRS` is `translation * rotation * scale`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TransformOrder {
+    #[default]
+    TRS,
+    TSR,
+    RTS,
+    RST,
+    STR,
+    SRT,
+}
+
+static GENERATION: AtomicU64 = AtomicU64::new(1);
+
+// unique across all transforms, so a replaced component never reuses an old generation
+pub(crate) fn next_generation() -> u64 {
+    GENERATION.fetch_add(1, Ordering::Relaxed)
+}
+
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Transform {
-    pub position: Vector3<f32>,
-    pub scale: Vector3<f32>,
-    pub rotation: Vector3<f32>,
+    position: Vector3<f32>,
+    scale: Vector3<f32>,
+    rotation: Quaternion<f32>,
+    // the origin that rotation and scale are applied around, in local space
+    #[cfg_attr(feature = "serde", serde(default))]
+    pivot: Vector3<f32>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    order: TransformOrder,
+    // bumped by every mutable access, `matrix` is reset with it
+    #[cfg_attr(feature = "serde", serde(skip, default = "next_generation"))]
+    generation: u64,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    matrix: OnceLock<Matrix4<f32>>,
 }
 
 impl Component for Transform {}
 
 impl Transform {
-    pub fn identity() -> Self {
+    fn new(
+        position: Vector3<f32>,
+        scale: Vector3<f32>,
+        rotation: Quaternion<f32>,
+        pivot: Vector3<f32>,
+        order: TransformOrder,
+    ) -> Self {
         Self {
-            position: Vector3::zeros(),
-            scale: Vector3::ones(),
-            rotation: Vector3::zeros(),
+            position,
+            scale,
+            rotation,
+            pivot,
+            order,
+            generation: next_generation(),
+            matrix: OnceLock::new(),
         }
     }
 
+    pub fn identity() -> Self {
+        Self::new(
+            Vector3::zeros(),
+            Vector3::ones(),
+            Quaternion::identity(),
+            Vector3::zeros(),
+            TransformOrder::default(),
+        )
+    }
+
     pub fn with_position(mut self, position: Vector3<f32>) -> Transform {
-        self.position = position;
+        self.set_position(position);
+        self
+    }
+    pub fn with_rotateion(mut self, rotation: Quaternion<f32>) -> Transform {
+        self.set_rotation(rotation);
         self
     }
-    pub fn with_rotateion(mut self, rotation: Vector3<f32>) -> Transform {
-        self.rotation = rotation;
+    pub fn with_euler_rotation(mut self, rotation: Vector3<f32>) -> Transform {
+        self.set_rotation(Quaternion::from_euler(&rotation));
         self
     }
     pub fn with_scale(mut self, scale: Vector3<f32>) -> Transform {
-        self.scale = scale;
+        self.set_scale(scale);
+        self
+    }
+    pub fn with_pivot(mut self, pivot: Vector3<f32>) -> Transform {
+        self.set_pivot(pivot);
+        self
+    }
+    pub fn with_order(mut self, order: TransformOrder) -> Transform {
+        self.set_order(order);
         self
     }
+
+    pub fn position(&self) -> &Vector3<f32> {
+        &self.position
+    }
+    pub fn scale(&self) -> &Vector3<f32> {
+        &self.scale
+    }
+    pub fn rotation(&self) -> &Quaternion<f32> {
+        &self.rotation
+    }
+    pub fn pivot(&self) -> &Vector3<f32> {
+        &self.pivot
+    }
+    pub fn order(&self) -> TransformOrder {
+        self.order
+    }
+
+    pub fn position_mut(&mut self) -> &mut Vector3<f32> {
+        self.mark_dirty();
+        &mut self.position
+    }
+    pub fn scale_mut(&mut self) -> &mut Vector3<f32> {
+        self.mark_dirty();
+        &mut self.scale
+    }
+    pub fn rotation_mut(&mut self) -> &mut Quaternion<f32> {
+        self.mark_dirty();
+        &mut self.rotation
+    }
+    pub fn pivot_mut(&mut self) -> &mut Vector3<f32> {
+        self.mark_dirty();
+        &mut self.pivot
+    }
+
+    pub fn set_position(&mut self, position: Vector3<f32>) {
+        *self.position_mut() = position;
+    }
+    pub fn set_scale(&mut self, scale: Vector3<f32>) {
+        *self.scale_mut() = scale;
+    }
+    pub fn set_rotation(&mut self, rotation: Quaternion<f32>) {
+        *self.rotation_mut() = rotation;
+    }
+    pub fn set_pivot(&mut self, pivot: Vector3<f32>) {
+        *self.pivot_mut() = pivot;
+    }
+    pub fn set_order(&mut self, order: TransformOrder) {
+        self.mark_dirty();
+        self.order = order;
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    fn mark_dirty(&mut self) {
+        self.generation = next_generation();
+        self.matrix = OnceLock::new();
+    }
+
+    // the order is taken from `self`
+    pub fn lerp(&self, other: &Transform, t: f32) -> Transform {
+        Self::new(
+            self.position.lerp(&other.position, t),
+            self.scale.lerp(&other.scale, t),
+            self.rotation.slerp(&other.rotation, t),
+            self.pivot.lerp(&other.pivot, t),
+            self.order,
+        )
+    }
+
+    // expects a `TRS` matrix, the pivot is reset
+    pub fn from_matrix(m: &Matrix4<f32>) -> Self {
+        let decomposition = m.decompose();
+        Self::new(
+            decomposition.translation,
+            decomposition.scale,
+            decomposition.rotation,
+            Vector3::zeros(),
+            TransformOrder::TRS,
+        )
+    }
+
+    // sets position, rotation and scale so that `to_matrix` gives `m`, the pivot and order are
+    // kept. shear can't be represented and is dropped, so a matrix that scales non-uniformly
+    // after a rotation (a child of a rotated, non-uniformly scaled parent) is only approximated
+    pub fn set_matrix(&mut self, m: &Matrix4<f32>) {
+        let local = translation_matrix(&-&self.pivot)
+            .mul_matrix4(m)
+            .mul_matrix4(&translation_matrix(&self.pivot));
+
+        // the linear part is `rotation * scale` or `scale * rotation` depending on the order,
+        // the second one is split through its transpose `rotation^-1 * scale`
+        let scale_first = matches!(
+            self.order,
+            TransformOrder::TSR | TransformOrder::STR | TransformOrder::SRT
+        );
+        let decomposition = if scale_first {
+            let mut transposed = Matrix4::identity();
+            for c in 0..3 {
+                for r in 0..3 {
+                    transposed[(c, r)] = local[(r, c)];
+                }
+            }
+            let mut decomposition = transposed.decompose();
+            decomposition.rotation = decomposition.rotation.conjugate();
+            decomposition
+        } else {
+            local.decompose()
+        };
+        self.rotation = decomposition.rotation;
+        self.scale = decomposition.scale;
+
+        let front = self.position_factor();
+        let at_origin = self.to_matrix_at(&Vector3::zeros());
+        let offset = Vector4::from([[
+            m[(3, 0)] - at_origin[(3, 0)],
+            m[(3, 1)] - at_origin[(3, 1)],
+            m[(3, 2)] - at_origin[(3, 2)],
+            0.0,
+        ]]);
+        if let Some(inverse) = front.inverse() {
+            let position = &inverse * offset;
+            self.position = [[position[0], position[1], position[2]]].into();
+        }
+        self.mark_dirty();
+    }
+
+    // the translation of the matrix is affine in `position`, this is its linear part, the
+    // product of the factors in front of the translation
+    fn position_factor(&self) -> Matrix4<f32> {
+        let r = Matrix4::from(&self.rotation);
+        let s = scale_matrix(&self.scale);
+        match self.order {
+            TransformOrder::TRS | TransformOrder::TSR => Matrix4::identity(),
+            TransformOrder::RTS => r,
+            TransformOrder::RST => r.mul_matrix4(&s),
+            TransformOrder::STR => s,
+            TransformOrder::SRT => s.mul_matrix4(&r),
+        }
+    }
+
+    // computed once per generation
+    pub fn matrix(&self) -> &Matrix4<f32> {
+        self.matrix.get_or_init(|| self.to_matrix())
+    }
+
+    // pivot * (product in `order`) * pivot^-1
+    pub fn to_matrix(&self) -> Matrix4<f32> {
+        self.to_matrix_at(&self.position)
+    }
+
+    // like `to_matrix` with `position` in place of `self.position`
+    pub fn to_matrix_at(&self, position: &Vector3<f32>) -> Matrix4<f32> {
+        let t = translation_matrix(position);
+        let r = Matrix4::from(&self.rotation);
+        let s = scale_matrix(&self.scale);
+
+        let (a, b, c) = match self.order {
+            TransformOrder::TRS => (&t, &r, &s),
+            TransformOrder::TSR => (&t, &s, &r),
+            TransformOrder::RTS => (&r, &t, &s),
+            TransformOrder::RST => (&r, &s, &t),
+            TransformOrder::STR => (&s, &t, &r),
+            TransformOrder::SRT => (&s, &r, &t),
+        };
+        let ans = a.mul_matrix4(b).mul_matrix4(c);
+
+        if self.pivot.iter().all(|(_, x)| *x == 0.0) {
+            return ans;
+        }
+        translation_matrix(&self.pivot)
+            .mul_matrix4(&ans)
+            .mul_matrix4(&translation_matrix(&-&self.pivot))
+    }
+}
+
+fn translation_matrix(v: &Vector3<f32>) -> Matrix4<f32> {
+    let mut ans = Matrix4::identity();
+    ans[(3, 0)] = *v.x();
+    ans[(3, 1)] = *v.y();
+    ans[(3, 2)] = *v.z();
+    ans
+}
+
+fn scale_matrix(v: &Vector3<f32>) -> Matrix4<f32> {
+    let mut ans = Matrix4::identity();
+    ans[(0, 0)] = *v.x();
+    ans[(1, 1)] = *v.y();
+    ans[(2, 2)] = *v.z();
+    ans
+}
+
+// the transform at the previous fixed tick, the renderer blends it with the current
+// `Transform` by `FrameInterpolation::alpha` instead of using `GlobalTransform`
+pub struct PreviousTransform(pub Transform);
+
+impl Component for PreviousTransform {}
+
+impl PreviousTransform {
+    pub fn snapshot(&mut self, current: &Transform) {
+        if self.0.generation != current.generation {
+            self.0 = current.clone();
+        }
+    }
+
+    // 0 is the previous tick and 1 is the current one, `None` when `current` didn't change
+    // since the snapshot so the renderer can keep using its uploaded instance
+    pub fn interpolate(&self, current: &Transform, alpha: f32) -> Option<Transform> {
+        (self.0.generation != current.generation).then(|| self.0.lerp(current, alpha))
+    }
+}
+
+impl From<&Transform> for PreviousTransform {
+    fn from(value: &Transform) -> Self {
+        Self(value.clone())
+    }
+}
+
+// run at the start of every fixed tick, before the tick moves anything, snapshots the camera too
+pub struct SnapshotTransformsSystem;
+
+impl AsyncSystem for SnapshotTransformsSystem {
+    type Query = (
+        ReadStorage<ComponentCollection<Transform>>,
+        WriteStorage<ComponentCollection<PreviousTransform>>,
+        WriteStorage<ResourceHolder<CameraResource>>,
+    );
+
+    fn run(&mut self, _ctx: &Context, (transforms, previous, camera): &mut Self::Query) {
+        let transforms = transforms.get();
+        for (e, previous) in previous.get().iter_mut() {
+            if let Some(current) = transforms.get(e) {
+                previous.snapshot(current);
+            }
+        }
+        if let Some(camera) = camera.get() {
+            camera.camera_mut().snapshot();
+        }
+    }
+}
+
+// a double precision replacement for `Transform::position` on root entities, it is ignored
+// on entities with a `Parent`. the children of the root inherit it through `GlobalTransform`
+pub struct WorldPosition(pub Vector3<f64>);
+
+impl Component for WorldPosition {}
+
+impl WorldPosition {
+    pub fn relative_to(&self, origin: &Vector3<f64>) -> Vector3<f32> {
+        relative_position(&self.0, origin)
+    }
+
+    // the world translation this position gives `transform`, in place of its own position
+    pub fn translation_for(&self, transform: &Transform) -> Vector3<f64> {
+        let factor = transform.position_factor();
+        let origin = transform.to_matrix_at(&Vector3::zeros());
+        let mut ans = Vector3::zeros();
+        for r in 0..3 {
+            ans[r] = origin[(3, r)] as f64;
+            for c in 0..3 {
+                ans[r] += factor[(c, r)] as f64 * self.0[c];
+            }
+        }
+        ans
+    }
+
+    // the matrix of `transform` at this position, translated by `-origin`
+    pub fn relative_matrix(&self, transform: &Transform, origin: &Vector3<f64>) -> Matrix4<f32> {
+        let mut m = transform.to_matrix_at(&Vector3::zeros());
+        let offset = relative_position(&self.translation_for(transform), origin);
+        for i in 0..3 {
+            m[(3, i)] = offset[i];
+        }
+        m
+    }
+}
+
+// the subtraction happens in f64 so only the small offset is rounded to f32
+pub fn relative_position(position: &Vector3<f64>, origin: &Vector3<f64>) -> Vector3<f32> {
+    let offset = position - origin;
+    [[offset[0] as f32, offset[1] as f32, offset[2] as f32]].into()
 }
 
 #[repr(C)]
-#[derive(Pod, Zeroable, Clone, Copy)]
+#[derive(Pod, Zeroable, Clone, Copy, Bufferable)]
+#[step_mode(instance)]
 pub struct InstanceTransform {
+    #[location(5)]
     data: [[f32; 4]; 4],
 }
 
 impl From<&Transform> for InstanceTransform {
     fn from(value: &Transform) -> Self {
-        let scale = Matrix4::from([
-            [*value.scale.x(), 0.0, 0.0, 0.0],
-            [0.0, *value.scale.y(), 0.0, 0.0],
-            [0.0, 0.0, *value.scale.z(), 0.0],
-            [
-                *value.position.x(),
-                *value.position.y(),
-                *value.position.z(),
-                1.,
-            ],
-        ]);
-
-        let rotate = value.rotation.euler_into_rotation_matrix4();
-
-        (scale + rotate).into()
-        // Matrix4::default().into()
+        value.matrix().clone().into()
     }
 }
 
@@ -81,37 +432,198 @@ impl Default for InstanceTransform {
     }
 }
 
-impl InstanceTransform {
-    const ATTRS: &[VertexAttribute] = &[
-        wgpu::VertexAttribute {
-            offset: 0,
-            shader_location: 5,
-            format: wgpu::VertexFormat::Float32x4,
-        },
-        wgpu::VertexAttribute {
-            offset: std::mem::size_of::<[f32; 4]>() as wgpu::BufferAddress,
-            shader_location: 6,
-            format: wgpu::VertexFormat::Float32x4,
-        },
-        wgpu::VertexAttribute {
-            offset: std::mem::size_of::<[f32; 8]>() as wgpu::BufferAddress,
-            shader_location: 7,
-            format: wgpu::VertexFormat::Float32x4,
-        },
-        wgpu::VertexAttribute {
-            offset: std::mem::size_of::<[f32; 12]>() as wgpu::BufferAddress,
-            shader_location: 8,
-            format: wgpu::VertexFormat::Float32x4,
-        },
-    ];
+#[test]
+fn test_matrix_round_trip() {
+    let transform = Transform::identity()
+        .with_position([[1.0, -2.0, 3.5]].into())
+        .with_euler_rotation([[0.3, -1.2, 0.7]].into())
+        .with_scale([[-2.0, 0.5, 3.0]].into());
+
+    let m = transform.to_matrix();
+    let back = Transform::from_matrix(&m);
+    for (pos, x) in back.to_matrix().iter() {
+        assert!((x - m[pos]).abs() < 1e-5);
+    }
+
+    let decomposition = m.decompose();
+    assert!(decomposition.negative_scale);
+    for i in 0..3 {
+        assert!((back.position[i] - transform.position[i]).abs() < 1e-5);
+        assert!((back.scale[i] - transform.scale[i]).abs() < 1e-5);
+    }
+    assert!(back.rotation.dot(&transform.rotation).abs() > 1.0 - 1e-5);
+    let euler = decomposition.euler(crate::math::transformable_matrices::EulerOrder::default());
+    for (i, angle) in [0.3, -1.2, 0.7].into_iter().enumerate() {
+        assert!((euler[i] - angle).abs() < 1e-4);
+    }
+
+    // the separately multiplied product
+    let t = Matrix4::from([
+        [1.0, 0.0, 0.0, 0.0],
+        [0.0, 1.0, 0.0, 0.0],
+        [0.0, 0.0, 1.0, 0.0],
+        [1.0, -2.0, 3.5, 1.0],
+    ]);
+    let s = Matrix4::from([
+        [-2.0, 0.0, 0.0, 0.0],
+        [0.0, 0.5, 0.0, 0.0],
+        [0.0, 0.0, 3.0, 0.0],
+        [0.0, 0.0, 0.0, 1.0],
+    ]);
+    let expected = t
+        .mul_matrix(&Matrix4::from(&transform.rotation))
+        .mul_matrix(&s);
+    for (pos, x) in m.iter() {
+        assert!((x - expected[pos]).abs() < 1e-5);
+    }
 }
 
-impl Bufferable for InstanceTransform {
-    fn describe<'a>() -> wgpu::VertexBufferLayout<'a> {
-        wgpu::VertexBufferLayout {
-            array_stride: std::mem::size_of::<InstanceTransform>() as BufferAddress,
-            step_mode: wgpu::VertexStepMode::Instance,
-            attributes: Self::ATTRS,
+#[test]
+fn test_set_matrix() {
+    let close =
+        |a: &Matrix4<f32>, b: &Matrix4<f32>| a.iter().all(|(pos, x)| (x - b[pos]).abs() < 1e-4);
+
+    for order in [
+        TransformOrder::TRS,
+        TransformOrder::TSR,
+        TransformOrder::RTS,
+        TransformOrder::RST,
+        TransformOrder::STR,
+        TransformOrder::SRT,
+    ] {
+        let target = Transform::identity()
+            .with_position([[1.0, -2.0, 3.0]].into())
+            .with_euler_rotation([[0.3, -0.8, 0.5]].into())
+            .with_scale([[2.0, 0.5, 1.5]].into())
+            .with_pivot([[0.5, 1.0, -1.0]].into())
+            .with_order(order);
+        let mut transform = Transform::identity()
+            .with_pivot([[0.5, 1.0, -1.0]].into())
+            .with_order(order);
+        transform.set_matrix(target.matrix());
+        assert!(close(transform.matrix(), target.matrix()), "{order:?}");
+        assert_eq!(transform.order(), order);
+        assert_eq!(transform.pivot()[1], 1.0);
+    }
+
+    // a rotation followed by a non-uniform scale is a shear, which is dropped
+    let sheared = scale_matrix(&[[3.0, 1.0, 1.0]].into()).mul_matrix4(
+        &Transform::identity()
+            .with_euler_rotation([[0.0, 0.0, 0.7]].into())
+            .to_matrix(),
+    );
+    let mut transform = Transform::identity();
+    transform.set_matrix(&sheared);
+    assert!(!close(transform.matrix(), &sheared));
+}
+
+#[test]
+fn test_instance_transform() {
+    let position: Vector3<f32> = [[0.5, 1.0, -4.0]].into();
+    let rotation = Quaternion::from_euler(&[[0.4, 1.1, -0.2]].into());
+    let scale: Vector3<f32> = [[2.0, 3.0, 0.5]].into();
+    let pivot: Vector3<f32> = [[1.0, -1.0, 2.0]].into();
+
+    let t = translation_matrix(&position);
+    let r = Matrix4::from(&rotation);
+    let s = scale_matrix(&scale);
+    let p = translation_matrix(&pivot);
+    let p_inv = translation_matrix(&-&pivot);
+
+    let cases = [
+        (TransformOrder::TRS, t.mul_matrix(&r).mul_matrix(&s)),
+        (TransformOrder::SRT, s.mul_matrix(&r).mul_matrix(&t)),
+        (TransformOrder::RTS, r.mul_matrix(&t).mul_matrix(&s)),
+    ];
+    for (order, expected) in cases {
+        let transform = Transform::identity()
+            .with_position(position.clone())
+            .with_rotateion(rotation)
+            .with_scale(scale.clone())
+            .with_order(order);
+        let data = InstanceTransform::from(&transform).data;
+        for ((c, r), x) in expected.iter() {
+            assert!((data[c][r] - x).abs() < 1e-5);
+        }
+
+        let expected = p.mul_matrix(&expected).mul_matrix(&p_inv);
+        let data = InstanceTransform::from(&transform.with_pivot(pivot.clone())).data;
+        for ((c, r), x) in expected.iter() {
+            assert!((data[c][r] - x).abs() < 1e-5);
         }
     }
 }
+
+#[test]
+fn test_camera_relative_position() {
+    let origin: Vector3<f64> = [[10_000_000.0, -5_000_000.0, 0.0]].into();
+    let position = WorldPosition([[10_000_000.25, -4_999_999.5, 3.125]].into());
+
+    let offset = position.relative_to(&origin);
+    assert_eq!([offset[0], offset[1], offset[2]], [0.25, 0.5, 3.125]);
+
+    let m = Transform::identity()
+        .with_scale([[2.0, 2.0, 2.0]].into())
+        .to_matrix_at(&offset);
+    assert_eq!([m[(3, 0)], m[(3, 1)], m[(3, 2)]], [0.25, 0.5, 3.125]);
+    assert_eq!(m[(0, 0)], 2.0);
+
+    // the world translation goes through the order and pivot like `Transform::position`
+    let transform = Transform::identity()
+        .with_euler_rotation([[0.2, 0.9, -0.4]].into())
+        .with_scale([[2.0, 1.0, 0.5]].into())
+        .with_pivot([[1.0, 0.0, -1.0]].into())
+        .with_order(TransformOrder::RST);
+    let position = WorldPosition([[40.5, -2.0, 3.0]].into());
+    let origin: Vector3<f64> = [[10.0, 20.0, 0.0]].into();
+    let mut expected = transform.to_matrix_at(&[[40.5, -2.0, 3.0]].into());
+    for i in 0..3 {
+        expected[(3, i)] -= origin[i] as f32;
+    }
+    let m = position.relative_matrix(&transform, &origin);
+    for (pos, x) in m.iter() {
+        assert!((x - expected[pos]).abs() < 1e-4);
+    }
+}
+
+#[test]
+fn test_generation() {
+    let mut transform = Transform::identity().with_position([[1.0, 2.0, 3.0]].into());
+    let generation = transform.generation();
+    assert_eq!(transform.matrix()[(3, 1)], 2.0);
+
+    // reading does not change the generation
+    let _ = transform.position();
+    let _ = transform.matrix();
+    assert_eq!(transform.generation(), generation);
+
+    *transform.position_mut() += Vector3::from([[0.0, 1.0, 0.0]]);
+    assert_ne!(transform.generation(), generation);
+    assert_eq!(transform.matrix()[(3, 1)], 3.0);
+
+    // a new component never reuses a generation
+    assert_ne!(Transform::identity().generation(), transform.generation());
+}
+
+#[test]
+fn test_previous_transform() {
+    let mut current = Transform::identity().with_position([[1.0, 0.0, 0.0]].into());
+    let mut previous = PreviousTransform::from(&current);
+
+    // nothing moved since the snapshot
+    assert!(previous.interpolate(&current, 0.5).is_none());
+
+    current.set_position([[3.0, 0.0, 0.0]].into());
+    let half = previous.interpolate(&current, 0.5).unwrap();
+    assert_eq!(half.position()[0], 2.0);
+    assert_eq!(
+        previous.interpolate(&current, 1.0).unwrap().position()[0],
+        3.0
+    );
+
+    // the next tick starts from the current transform
+    previous.snapshot(&current);
+    assert_eq!(previous.0.position()[0], 3.0);
+    assert_eq!(previous.0.generation(), current.generation());
+    assert!(previous.interpolate(&current, 0.5).is_none());
+}
@@ -0,0 +1,155 @@
+use std::{num::NonZeroU64, ops::Range, sync::Arc};
+
+use bytemuck::{Pod, Zeroable};
+use wgpu::{
+    util::StagingBelt, Buffer, BufferAddress, CommandBuffer, CommandEncoder,
+    CommandEncoderDescriptor, Device, COPY_BUFFER_ALIGNMENT,
+};
+
+use super::buffers::BufferContainer;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct UploadStats {
+    pub writes: u64,
+    pub bytes: u64,
+}
+
+impl UploadStats {
+    fn add(&mut self, other: &UploadStats) {
+        self.writes += other.writes;
+        self.bytes += other.bytes;
+    }
+}
+
+// batches the writes of a frame through a `StagingBelt`, the bytes are written straight into
+// mapped staging chunks and copied to the targets by the command buffer `finish` returns.
+// `recall` maps the chunks again once that command buffer was submitted, so they are reused
+pub struct Uploader {
+    device: Arc<Device>,
+    belt: StagingBelt,
+    encoder: Option<CommandEncoder>,
+    frame: UploadStats,
+    last_frame: UploadStats,
+    total: UploadStats,
+}
+
+impl Uploader {
+    pub const DEFAULT_CHUNK_SIZE: BufferAddress = 1 << 16;
+
+    pub fn new(device: Arc<Device>, chunk_size: BufferAddress) -> Self {
+        Self {
+            device,
+            belt: StagingBelt::new(chunk_size.max(COPY_BUFFER_ALIGNMENT)),
+            encoder: None,
+            frame: Default::default(),
+            last_frame: Default::default(),
+            total: Default::default(),
+        }
+    }
+
+    pub fn device(&self) -> &Device {
+        &self.device
+    }
+
+    // writes `data` to the elements `range` of `buffer`, the byte offset and size have to be
+    // multiples of `COPY_BUFFER_ALIGNMENT` like for `Queue::write_buffer`
+    pub fn write<T: Pod + Zeroable>(
+        &mut self,
+        buffer: &BufferContainer<T>,
+        range: Range<u64>,
+        data: &[T],
+    ) {
+        assert_eq!(
+            range.end - range.start,
+            data.len() as u64,
+            "the range and the data have different lengths"
+        );
+        let size = std::mem::size_of::<T>() as BufferAddress;
+        self.write_bytes(
+            buffer.buffer(),
+            range.start * size,
+            bytemuck::cast_slice(data),
+        );
+    }
+
+    pub fn write_bytes(&mut self, target: &Buffer, offset: BufferAddress, bytes: &[u8]) {
+        let Some(size) = NonZeroU64::new(bytes.len() as BufferAddress) else {
+            return;
+        };
+        assert!(
+            is_aligned(offset, size.get()),
+            "the offset and size of a write have to be multiples of 4 bytes, got {offset} and {size}"
+        );
+        let encoder = encoder(&mut self.encoder, &self.device);
+        self.belt
+            .write_buffer(encoder, target, offset, size, &self.device)
+            .copy_from_slice(bytes);
+
+        self.frame.writes += 1;
+        self.frame.bytes += size.get();
+    }
+
+    // the encoder the copies are recorded in, commands recorded here run in order with them
+    pub fn encoder(&mut self) -> &mut CommandEncoder {
+        encoder(&mut self.encoder, &self.device)
+    }
+
+    // unmaps the staging chunks, the returned commands have to be submitted before any commands
+    // that read the written buffers and `recall` called after that
+    pub fn finish(&mut self) -> Option<CommandBuffer> {
+        self.belt.finish();
+        self.total.add(&self.frame);
+        self.last_frame = std::mem::take(&mut self.frame);
+        self.encoder.take().map(|x| x.finish())
+    }
+
+    // maps the chunks of the submitted uploads again so the next frames can reuse them
+    pub fn recall(&mut self) {
+        self.belt.recall();
+    }
+
+    // the writes of the last finished frame
+    pub fn last_frame(&self) -> UploadStats {
+        self.last_frame
+    }
+
+    pub fn total(&self) -> UploadStats {
+        self.total
+    }
+}
+
+fn encoder<'a>(encoder: &'a mut Option<CommandEncoder>, device: &Device) -> &'a mut CommandEncoder {
+    encoder.get_or_insert_with(|| {
+        device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("upload encoder"),
+        })
+    })
+}
+
+fn is_aligned(offset: BufferAddress, size: BufferAddress) -> bool {
+    offset % COPY_BUFFER_ALIGNMENT == 0 && size % COPY_BUFFER_ALIGNMENT == 0
+}
+
+#[test]
+fn test_upload_stats() {
+    assert!(is_aligned(256, 64));
+    assert!(!is_aligned(256, 6));
+    assert!(!is_aligned(2, 4));
+
+    let mut total = UploadStats::default();
+    total.add(&UploadStats {
+        writes: 2,
+        bytes: 128,
+    });
+    total.add(&UploadStats {
+        writes: 1,
+        bytes: 4,
+    });
+    assert_eq!(
+        total,
+        UploadStats {
+            writes: 3,
+            bytes: 132
+        }
+    );
+}
@@ -1,19 +1,20 @@
 use std::f32::consts::PI;
 
 use bytemuck::{Pod, Zeroable};
-use lazy_static::lazy_static;
 use matrix_engine::components::resources::Resource;
-use wgpu::{BindGroupEntry, BindGroupLayoutEntry, BufferUsages, Queue, ShaderStages};
+use wgpu::{BindGroupEntry, BindGroupLayoutEntry, BufferUsages, ShaderStages};
 
 use crate::{
     math::{
+        geometry::Frustum,
         matrices::{Matrix4, Vector3},
-        transformable_matrices::{Prespective, TransformMatrix},
+        transformable_matrices::{DepthMode, Prespective, Projection, TransformMatrix},
     },
     pipelines::{
         bind_groups::{BindDataEntry, BindGroupContainer},
         buffers::{BufferContainer, Bufferable},
-        transform::Transform,
+        transform::{next_generation, Transform},
+        uploader::Uploader,
     },
 };
 
@@ -46,9 +47,9 @@ impl Bufferable for CameraUniform {
 impl BindDataEntry for CameraUniform {
     type Args<'a> = &'a BufferContainer<CameraUniform>;
 
-    fn layout_entries() -> Box<dyn Iterator<Item = BindGroupLayoutEntry>> {
+    fn layout_entries(binding: u32) -> Box<dyn Iterator<Item = BindGroupLayoutEntry>> {
         Box::new(std::iter::once(BindGroupLayoutEntry {
-            binding: 0,
+            binding,
             visibility: ShaderStages::VERTEX,
             ty: wgpu::BindingType::Buffer {
                 ty: wgpu::BufferBindingType::Uniform,
@@ -59,9 +60,12 @@ impl BindDataEntry for CameraUniform {
         }))
     }
 
-    fn entries<'a>(args: Self::Args<'a>) -> Box<dyn Iterator<Item = BindGroupEntry<'a>> + 'a> {
+    fn entries<'a>(
+        binding: u32,
+        args: Self::Args<'a>,
+    ) -> Box<dyn Iterator<Item = BindGroupEntry<'a>> + 'a> {
         Box::new(std::iter::once(BindGroupEntry {
-            binding: 0,
+            binding,
             resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
                 buffer: args.buffer(),
                 offset: 0,
@@ -71,48 +75,106 @@ impl BindDataEntry for CameraUniform {
     }
 }
 
-lazy_static! {
-    static ref OPENGL_TO_WGPU_MATRIX: Matrix4<f32> = Matrix4::from([
-        [1.0, 0.0, 0.0, 0.0],
-        [0.0, 1.0, 0.0, 0.0],
-        [0.0, 0.0, 0.5, 0.0],
-        [0.0, 0.0, 0.5, 1.0],
-    ]);
-}
-
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Camera {
-    pub prespective: Prespective<f32>,
+    pub projection: Projection<f32>,
     pub transform: Transform,
+    pub depth: DepthMode,
+    // when set, the camera is at `world_position + transform.position`, the view is built at the
+    // origin and every instance is offset by that sum, see `render_origin`
+    pub world_position: Option<Vector3<f64>>,
+    // the transform at the previous fixed tick, taken by `SnapshotTransformsSystem`
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub previous_transform: Option<Transform>,
 }
 
 impl Camera {
-    pub fn new(transform: Transform, prespective: Prespective<f32>) -> Self {
+    pub fn new(transform: Transform, projection: Projection<f32>) -> Self {
         Self {
-            prespective,
+            projection,
             transform,
+            depth: DepthMode::default(),
+            world_position: None,
+            previous_transform: None,
         }
     }
+    pub fn with_depth(mut self, depth: DepthMode) -> Self {
+        self.depth = depth;
+        self
+    }
+    pub fn with_world_position(mut self, world_position: Vector3<f64>) -> Self {
+        self.world_position = Some(world_position);
+        self
+    }
+    pub fn is_camera_relative(&self) -> bool {
+        self.world_position.is_some()
+    }
+    // the world position instances are rendered relative to, in camera relative mode
+    pub fn render_origin(&self) -> Option<Vector3<f64>> {
+        let position = self.transform.position();
+        self.world_position.as_ref().map(|world_position| {
+            let offset: Vector3<f64> =
+                [[position[0] as f64, position[1] as f64, position[2] as f64]].into();
+            world_position + &offset
+        })
+    }
+    pub fn snapshot(&mut self) {
+        self.previous_transform = Some(self.transform.clone());
+    }
     pub fn generate_transform_matrix(&self) -> Matrix4<f32> {
-        let rotate = self.transform.rotation.euler_into_rotation_matrix3();
-
-        let dir = rotate * Vector3::from([[0., 0., -1.]]);
+        self.generate_interpolated_matrix(1.0)
+    }
+    // blends the previous snapshot and the current transform, 0 is the previous tick
+    pub fn generate_interpolated_matrix(&self, alpha: f32) -> Matrix4<f32> {
+        let transform = match &self.previous_transform {
+            Some(previous) if alpha < 1.0 => previous.lerp(&self.transform, alpha),
+            _ => self.transform.clone(),
+        };
+        let dir = transform
+            .rotation()
+            .rotate_vector(&Vector3::from([[0., 0., -1.]]));
 
-        let view = Matrix4::look_at_rh(
-            &self.transform.position,
-            &(&self.transform.position + &dir),
-            &Vector3::up(),
-        );
+        // relative to `render_origin`, which only follows the current transform
+        let eye = match self.world_position {
+            Some(_) => transform.position() - self.transform.position(),
+            None => transform.position().clone(),
+        };
+        let view = Matrix4::look_at_rh(&eye, &(&eye + &dir), &Vector3::up());
 
-        let proj: Matrix4<f32> = &*OPENGL_TO_WGPU_MATRIX * Matrix4::from(&self.prespective) * view;
+        let proj: Matrix4<f32> = self.projection.wgpu_matrix(self.depth) * view;
 
         proj
     }
+
+    // in camera relative mode the frustum is relative to `render_origin` too
+    pub fn frustum(&self) -> Frustum {
+        Frustum::from_matrix(&self.generate_transform_matrix())
+    }
+}
+
+// gives the render origin a generation that only changes when the origin moves
+#[derive(Default)]
+struct OriginGeneration {
+    origin: Option<Vector3<f64>>,
+    generation: u64,
+}
+
+impl OriginGeneration {
+    fn update(&mut self, origin: Option<&Vector3<f64>>) -> u64 {
+        let changed = self.origin.as_ref().map(|x| x.as_arrays()) != origin.map(|x| x.as_arrays());
+        if changed || self.generation == 0 {
+            self.origin = origin.cloned();
+            self.generation = next_generation();
+        }
+        self.generation
+    }
 }
 
 pub struct CameraResource {
     group: BindGroupContainer<(CameraUniform,)>,
     camera_buffer: BufferContainer<CameraUniform>,
     camera: Camera,
+    origin: OriginGeneration,
 }
 
 impl CameraResource {
@@ -127,6 +189,13 @@ impl CameraResource {
     pub fn camera_mut(&mut self) -> &mut Camera {
         &mut self.camera
     }
+
+    // `Camera::render_origin` with a generation that changes whenever the origin moves
+    pub fn render_origin(&mut self) -> Option<(Vector3<f64>, u64)> {
+        let origin = self.camera.render_origin();
+        let generation = self.origin.update(origin.as_ref());
+        origin.map(|origin| (origin, generation))
+    }
 }
 
 impl CameraResource {
@@ -152,25 +221,66 @@ impl CameraResource {
                 aspect: 1.0,
                 near: 0.1,
                 far: 1000.0,
-            },
+            }
+            .into(),
         );
 
         Self {
             group,
             camera_buffer: buffer,
             camera,
+            origin: Default::default(),
         }
     }
 
-    pub fn update_buffer(&mut self, queue: &Queue) {
-        let data = self.camera.generate_transform_matrix();
+    pub fn update_buffer(&mut self, uploader: &mut Uploader, alpha: f32) {
+        let mut data = CameraUniform::default();
+        data.read_from_matrix(&self.camera.generate_interpolated_matrix(alpha));
 
-        queue.write_buffer(
-            self.camera_buffer.buffer(),
-            0,
-            bytemuck::bytes_of(&data.into_arrays()),
-        );
+        uploader.write(&self.camera_buffer, 0..1, &[data]);
     }
 }
 
 impl Resource for CameraResource {}
+
+#[test]
+fn test_render_origin() {
+    let projection = || {
+        Prespective {
+            fovy_rad: 1.0,
+            aspect: 1.0,
+            near: 0.1,
+            far: 100.0,
+        }
+        .into()
+    };
+    let transform = Transform::identity().with_position([[1.5, 0.0, -2.0]].into());
+    let mut camera = Camera::new(transform, projection());
+    assert!(camera.render_origin().is_none());
+    let view = camera.generate_transform_matrix();
+
+    // the position is an offset from the world position, and the view is built at the origin
+    camera.world_position = Some([[1e9, 0.0, 0.0]].into());
+    let origin = camera.render_origin().unwrap();
+    assert_eq!(origin.as_arrays(), &[[1e9 + 1.5, 0.0, -2.0]]);
+    let at_origin = Camera::new(Transform::identity(), projection()).generate_transform_matrix();
+    assert_eq!(
+        camera.generate_transform_matrix().as_arrays(),
+        at_origin.as_arrays()
+    );
+    assert_ne!(view.as_arrays(), at_origin.as_arrays());
+}
+
+#[test]
+fn test_origin_generation() {
+    let mut tracker = OriginGeneration::default();
+    let origin: Vector3<f64> = [[1e9, 0.0, 0.0]].into();
+    let generation = tracker.update(Some(&origin));
+    assert_ne!(generation, 0);
+    assert_eq!(tracker.update(Some(&origin)), generation);
+
+    let moved: Vector3<f64> = [[1e9, 1.0, 0.0]].into();
+    let moved_generation = tracker.update(Some(&moved));
+    assert_ne!(moved_generation, generation);
+    assert_ne!(tracker.update(None), moved_generation);
+}
@@ -1,10 +1,10 @@
 use std::any::TypeId;
 
 use matrix_engine::components::component::Component;
-use wgpu::{Device, Queue};
 
 use crate::pipelines::{
-    buffers::{Vertex, VertexBuffer},
+    buffers::Vertex,
+    geometry_arena::MeshData,
     instance_manager::VertexStructure,
 };
 
@@ -32,8 +32,8 @@ impl RenderObject {
     pub fn structure_type_id(&self) -> TypeId {
         self.buffer.type_id()
     }
-    pub fn create_buffer(&self, device: &Device, queue: &Queue) -> VertexBuffer<Vertex> {
-        self.buffer.craete_buffer(device, queue)
+    pub fn mesh_data(&self) -> MeshData<Vertex> {
+        self.buffer.mesh_data()
     }
 }
 
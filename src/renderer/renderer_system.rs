@@ -1,14 +1,22 @@
 use std::sync::Arc;
 
 use crate::{
+    math::{
+        matrices::{Matrix4, Vector3},
+        transformable_matrices::DepthMode,
+    },
     pipelines::{
         buffers::Vertex,
         group_layout_manager::BindGroupLayoutManager,
-        instance_manager::InstanceManager,
+        hierarchy::{GlobalTransform, Parent},
+        instance_manager::{InstanceAttributes, InstanceGeneration, InstanceManager, MainInstance},
         matrix_render_pipeline::{MatrixRenderPipeline, MatrixRenderPipelineArgs},
         shaders::ShaderConfig,
         texture::MatrixTexture,
-        transform::{InstanceTransform, Transform},
+        transform::{
+            relative_position, InstanceTransform, PreviousTransform, Transform, WorldPosition,
+        },
+        uploader::Uploader,
     },
     shaders,
 };
@@ -26,9 +34,9 @@ use matrix_engine::{
 };
 use matrix_engine::{dispatchers::context::Context, events::event_registry::EventRegistry};
 use wgpu::{
-    Backends, Color, CommandEncoderDescriptor, Device, DeviceDescriptor, Features, Instance,
-    Limits, Operations, PowerPreference, Queue, Surface, SurfaceConfiguration, SurfaceError,
-    TextureUsages,
+    Backends, Color, CommandEncoderDescriptor, CompareFunction, Device, DeviceDescriptor, Features,
+    Instance, Limits, Operations, PowerPreference, Queue, Surface, SurfaceConfiguration,
+    SurfaceError, TextureUsages,
 };
 use winit::dpi::PhysicalSize;
 
@@ -50,7 +58,8 @@ pub struct RendererResource {
     config: SurfaceConfiguration,
     background_color: Color,
     group_layout_manager: BindGroupLayoutManager,
-    instance_manager: InstanceManager,
+    instance_manager: InstanceManager<MainInstance>,
+    uploader: Uploader,
     depth_texture: MatrixTexture,
 }
 
@@ -121,6 +130,7 @@ impl RendererResource {
             surface,
             background_color: args.background_color,
             group_layout_manager: BindGroupLayoutManager::new(device.clone()),
+            uploader: Uploader::new(device.clone(), Uploader::DEFAULT_CHUNK_SIZE),
             instance_manager: InstanceManager::new(device, queue),
         }
     }
@@ -147,13 +157,34 @@ impl RendererResource {
         &mut self.group_layout_manager
     }
 
-    pub fn instance_manager_mut(&mut self) -> &mut InstanceManager {
+    pub fn instance_manager_mut(&mut self) -> &mut InstanceManager<MainInstance> {
         &mut self.instance_manager
     }
+
+    pub fn uploader(&self) -> &Uploader {
+        &self.uploader
+    }
+
+    pub fn uploader_mut(&mut self) -> &mut Uploader {
+        &mut self.uploader
+    }
 }
 
 impl Resource for RendererResource {}
 
+// how far the rendered frame is between the previous fixed tick (0) and the current one (1)
+pub struct FrameInterpolation {
+    pub alpha: f32,
+}
+
+impl Default for FrameInterpolation {
+    fn default() -> Self {
+        Self { alpha: 1.0 }
+    }
+}
+
+impl Resource for FrameInterpolation {}
+
 pub struct RendererSystem;
 
 impl AsyncSystem for RendererSystem {
@@ -164,11 +195,19 @@ impl AsyncSystem for RendererSystem {
             WriteStorage<ResourceHolder<RendererResource>>,
             WriteStorage<ResourceHolder<MainPipeline>>,
             WriteStorage<ResourceHolder<CameraResource>>,
+            ReadStorage<ComponentCollection<GlobalTransform>>,
+            ReadStorage<ComponentCollection<WorldPosition>>,
         ),
         ComponentGroup<(
             ReadStorage<ComponentCollection<RenderObject>>,
             ReadStorage<ComponentCollection<Transform>>,
         )>,
+        (
+            ReadStorage<ResourceHolder<FrameInterpolation>>,
+            ReadStorage<ComponentCollection<PreviousTransform>>,
+            ReadStorage<ComponentCollection<InstanceAttributes>>,
+            ReadStorage<ComponentCollection<Parent>>,
+        ),
     );
 
     fn run(
@@ -176,8 +215,16 @@ impl AsyncSystem for RendererSystem {
         ctx: &Context,
         (
             events,
-            (window_resource, render_resource, main_pipeline, camera_resource),
+            (
+                window_resource,
+                render_resource,
+                main_pipeline,
+                camera_resource,
+                globals,
+                world_positions,
+            ),
             objects,
+            (interpolation, previous_transforms, attributes, parents),
         ): &mut Self::Query,
     ) {
         let Some(window_resource) = window_resource.get() else { return; };
@@ -192,35 +239,6 @@ impl AsyncSystem for RendererSystem {
                 },
             })
         });
-        let main_pipeline = ctx.get_or_insert_resource_with(main_pipeline.holder_mut(), || {
-            MainPipeline::new(MatrixRenderPipelineArgs {
-                device: &render_resource.device,
-                shaders: shaders!(&render_resource.device, "shaders.wgsl", "main shaders"),
-                shader_config: ShaderConfig {
-                    fragment_main: "f_main".to_owned(),
-                    vertex_main: "v_main".to_owned(),
-                },
-                pipe_label: "main pipeline",
-                group_label: "main groups",
-                surface_config: &render_resource.config,
-                primitive_state: wgpu::PrimitiveState {
-                    topology: wgpu::PrimitiveTopology::TriangleList,
-                    strip_index_format: None,
-                    front_face: wgpu::FrontFace::Ccw,
-                    cull_mode: Some(wgpu::Face::Back),
-                    polygon_mode: wgpu::PolygonMode::Fill,
-                    unclipped_depth: false,
-                    conservative: false,
-                },
-                depth_stencil: Some(wgpu::DepthStencilState {
-                    format: MatrixTexture::DEPTH_FORMAT,
-                    depth_write_enabled: true,
-                    depth_compare: wgpu::CompareFunction::Less,
-                    stencil: Default::default(),
-                    bias: Default::default(),
-                }),
-            })
-        });
         let events = events.get().get_window_events(window_resource.id());
         if let Some(size) = events.is_resized() {
             render_resource.resize(size);
@@ -229,11 +247,24 @@ impl AsyncSystem for RendererSystem {
         let camera_resource = ctx.get_or_insert_resource_with(camera_resource.holder_mut(), || {
             CameraResource::new(render_resource)
         });
+        let depth_mode = camera_resource.camera().depth;
 
-        camera_resource.update_buffer(render_resource.queue());
+        let main_pipeline = ctx.get_or_insert_resource_with(main_pipeline.holder_mut(), || {
+            MainPipeline::new(main_pipeline_args(render_resource, depth_mode))
+        });
+        if main_pipeline.depth_stencil().map(|x| x.depth_compare) != Some(depth_compare(depth_mode))
+        {
+            *main_pipeline = MainPipeline::new(main_pipeline_args(render_resource, depth_mode));
+        }
+
+        let alpha = interpolation.get().map(|x| x.alpha).unwrap_or(1.0);
+        camera_resource.update_buffer(&mut render_resource.uploader, alpha);
         {
             let s = window_resource.size();
-            camera_resource.camera_mut().prespective.aspect = s.width as f32 / s.height as f32;
+            camera_resource
+                .camera_mut()
+                .projection
+                .set_aspect(s.width as f32 / s.height as f32);
         }
         let current = render_resource.surface.get_current_texture();
         if let Ok(output) = current {
@@ -259,7 +290,7 @@ impl AsyncSystem for RendererSystem {
                     depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
                         view: render_resource.depth_texture.view(),
                         depth_ops: Some(Operations {
-                            load: wgpu::LoadOp::Clear(1.0),
+                            load: wgpu::LoadOp::Clear(depth_mode.clear_value()),
                             store: true,
                         }),
                         stencil_ops: None,
@@ -267,11 +298,65 @@ impl AsyncSystem for RendererSystem {
                 });
 
                 main_pipeline.begin(&mut pass);
-                objects.iter().for_each(|(_, data, trans)| {
+                let globals = globals.get();
+                let world_positions = world_positions.get();
+                let previous_transforms = previous_transforms.get();
+                let attributes = attributes.get();
+                let parents = parents.get();
+                let (origin, origin_generation) = match camera_resource.render_origin() {
+                    Some((origin, generation)) => (Some(origin), generation),
+                    None => (None, 0),
+                };
+                objects.iter().for_each(|(e, data, trans)| {
+                    let interpolated = previous_transforms
+                        .get(e)
+                        .filter(|_| alpha < 1.0)
+                        .and_then(|previous| previous.interpolate(trans, alpha));
+                    let global = globals.get(e);
+                    let world_position = world_positions.get(e);
+                    let attributes = attributes.get(e);
+                    // an interpolated transform changes every frame, and a `WorldPosition` has no
+                    // generation so it is only tracked through its `GlobalTransform`
+                    let generation = match (&origin, global) {
+                        _ if interpolated.is_some() => None,
+                        (Some(_), None) if world_position.is_some() => None,
+                        _ => Some(InstanceGeneration {
+                            transform: global.map_or(trans.generation(), |x| x.generation()),
+                            attributes: attributes.map_or(0, |x| x.generation()),
+                            origin: origin_generation,
+                        }),
+                    };
                     render_resource.instance_manager.registr_object(
+                        *e,
                         data,
-                        trans,
+                        generation,
+                        || {
+                            let transform = match (&interpolated, &origin, global) {
+                                // the interpolated local transform under the parent's world matrix
+                                (Some(local), origin, _) => {
+                                    let parent = parents
+                                        .get(e)
+                                        .and_then(|parent| globals.get(parent.entity()));
+                                    InstanceTransform::from(interpolated_matrix(
+                                        local,
+                                        world_position.filter(|_| parents.get(e).is_none()),
+                                        parent,
+                                        origin.as_ref(),
+                                    ))
+                                }
+                                (None, Some(origin), _) => InstanceTransform::from(
+                                    camera_relative_matrix(trans, world_position, global, origin),
+                                ),
+                                (None, None, Some(global)) => global.into(),
+                                (None, None, None) => trans.into(),
+                            };
+                            match attributes {
+                                Some(attributes) => MainInstance::new(transform, attributes),
+                                None => transform.into(),
+                            }
+                        },
                         &mut render_resource.group_layout_manager,
+                        &mut render_resource.uploader,
                     );
                     // main_pipeline
                     //     .apply_groups(&mut pass, (data.texture_group(), camera_resource.group()));
@@ -285,26 +370,34 @@ impl AsyncSystem for RendererSystem {
                     //     0..1,
                     // );
                 });
-                render_resource.instance_manager.prepare();
+                render_resource
+                    .instance_manager
+                    .prepare(&mut render_resource.uploader);
 
+                render_resource
+                    .instance_manager
+                    .arena()
+                    .apply_to_pass(&mut pass, 0);
                 for (i, instances) in render_resource.instance_manager.iter_data() {
                     main_pipeline
                         .apply_groups(&mut pass, (i.texture_group(), camera_resource.group()));
-                    main_pipeline.set_vertex_buffer(&mut pass, i.structure_buffer(), 0);
-                    main_pipeline.set_buffer(&mut pass, i.transform_buffer(), 1);
+                    main_pipeline.set_buffer(&mut pass, i.instance_buffer(), 1);
 
                     main_pipeline.draw_indexed(
                         &mut pass,
-                        0..i.structure_buffer().size() as u32,
+                        i.mesh().index_range(),
+                        i.mesh().base_vertex(),
                         0..instances,
                     );
                 }
             }
             render_resource.instance_manager.clear();
 
+            let uploads = render_resource.uploader.finish();
             render_resource
                 .queue
-                .submit(std::iter::once(encoder.finish()));
+                .submit(uploads.into_iter().chain(std::iter::once(encoder.finish())));
+            render_resource.uploader.recall();
             output.present();
         } else if let Err(err) = current {
             match err {
@@ -319,5 +412,83 @@ impl AsyncSystem for RendererSystem {
     }
 }
 
+fn camera_relative_matrix(
+    transform: &Transform,
+    world_position: Option<&WorldPosition>,
+    global: Option<&GlobalTransform>,
+    origin: &Vector3<f64>,
+) -> Matrix4<f32> {
+    if let Some(global) = global {
+        return global.relative_to(origin);
+    }
+    if let Some(world_position) = world_position {
+        return world_position.relative_matrix(transform, origin);
+    }
+    let mut m = transform.matrix().clone();
+    let position: Vector3<f64> = [[m[(3, 0)] as f64, m[(3, 1)] as f64, m[(3, 2)] as f64]].into();
+    let offset = relative_position(&position, origin);
+    for i in 0..3 {
+        m[(3, i)] = offset[i];
+    }
+    m
+}
+
+fn interpolated_matrix(
+    local: &Transform,
+    world_position: Option<&WorldPosition>,
+    parent: Option<&GlobalTransform>,
+    origin: Option<&Vector3<f64>>,
+) -> Matrix4<f32> {
+    match (parent, origin) {
+        (Some(parent), Some(origin)) => parent.relative_to(origin).mul_matrix4(local.matrix()),
+        (Some(parent), None) => parent.matrix().mul_matrix4(local.matrix()),
+        (None, Some(origin)) => camera_relative_matrix(local, world_position, None, origin),
+        (None, None) => match world_position {
+            Some(world_position) => world_position.relative_matrix(local, &Vector3::zeros()),
+            None => local.matrix().clone(),
+        },
+    }
+}
+
+fn depth_compare(depth_mode: DepthMode) -> CompareFunction {
+    match depth_mode {
+        DepthMode::Standard => CompareFunction::Less,
+        DepthMode::Reversed => CompareFunction::Greater,
+    }
+}
+
+fn main_pipeline_args(
+    render_resource: &RendererResource,
+    depth_mode: DepthMode,
+) -> MatrixRenderPipelineArgs<'_> {
+    MatrixRenderPipelineArgs {
+        device: &render_resource.device,
+        shaders: shaders!(&render_resource.device, "shaders.wgsl", "main shaders"),
+        shader_config: ShaderConfig {
+            fragment_main: "f_main".to_owned(),
+            vertex_main: "v_main".to_owned(),
+        },
+        pipe_label: "main pipeline",
+        group_label: "main groups",
+        surface_config: &render_resource.config,
+        primitive_state: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            strip_index_format: None,
+            front_face: wgpu::FrontFace::Ccw,
+            cull_mode: Some(wgpu::Face::Back),
+            polygon_mode: wgpu::PolygonMode::Fill,
+            unclipped_depth: false,
+            conservative: false,
+        },
+        depth_stencil: Some(wgpu::DepthStencilState {
+            format: MatrixTexture::DEPTH_FORMAT,
+            depth_write_enabled: true,
+            depth_compare: depth_compare(depth_mode),
+            stencil: Default::default(),
+            bias: Default::default(),
+        }),
+    }
+}
+
 pub(super) type MainPipeline =
-    MatrixRenderPipeline<(Vertex, InstanceTransform), ((MatrixTexture,), (CameraUniform,))>;
+    MatrixRenderPipeline<(Vertex, MainInstance), ((MatrixTexture,), (CameraUniform,))>;
@@ -12,6 +12,12 @@ struct InstanceTransform {
     @location(8) mat4: vec4<f32>,
 }
 
+struct InstanceAttributes {
+    @location(9) tint: vec4<f32>,
+    // xy is the offset and zw the scale
+    @location(10) uv_rect: vec4<f32>,
+}
+
 fn into_mat(m:InstanceTransform) -> mat4x4<f32> {
     return mat4x4<f32>(
         m.mat1,
@@ -25,6 +31,7 @@ fn into_mat(m:InstanceTransform) -> mat4x4<f32> {
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
     @location(0) tex_coords: vec2<f32>,
+    @location(1) tint: vec4<f32>,
 }
 
 @group(1) @binding(0)
@@ -34,9 +41,11 @@ var<uniform> camera_proj: mat4x4<f32>;
 fn v_main(
     model: VertexInput,
     instance: InstanceTransform,
+    attributes: InstanceAttributes,
 ) -> VertexOutput {
     var out: VertexOutput;
-    out.tex_coords = model.tex_coords;
+    out.tex_coords = attributes.uv_rect.xy + model.tex_coords * attributes.uv_rect.zw;
+    out.tint = attributes.tint;
     out.clip_position = camera_proj * into_mat(instance) * vec4<f32>(model.position, 1.0);
     return out;
 }
@@ -50,5 +59,5 @@ var s_diffuse: sampler;
 
 @fragment
 fn f_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    return textureSample(t_diffuse, s_diffuse, in.tex_coords);
+    return textureSample(t_diffuse, s_diffuse, in.tex_coords) * in.tint;
 }
\ No newline at end of file
@@ -13,8 +13,10 @@ use matrix_engine::{
     schedulers::multi_threaded_scheduler::MultiThreadedScheduler,
 };
 use matrix_renderer::{
-    math::{matrices::{Vector3, IntoMatrix}, vectors::Vector3D},
-    pipelines::{structures::plain::Plain, transform::Transform},
+    math::{matrices::{Vector3, IntoMatrix}, quaternions::Quaternion, vectors::Vector3D},
+    pipelines::{
+        hierarchy::TransformPropagationSystem, structures::plain::Plain, transform::Transform,
+    },
     renderer::{
         camera::CameraResource,
         render_object::RenderObject,
@@ -50,7 +52,7 @@ impl AsyncSystem for CreateDataSystem {
                     Transform::identity()
                         .with_position([[x as f32, 0., -z as f32]].into())
                         .with_scale([[r.gen::<f32>(), r.gen::<f32>(), r.gen::<f32>()]].into_matrix()*2.0)
-                        .with_rotateion(
+                        .with_euler_rotation(
                             [[
                                 r.gen_range(0.0..(2.0 * PI)),
                                 r.gen_range(0.0..(2.0 * PI)),
@@ -120,13 +122,14 @@ impl AsyncSystem for CameraPlayerSystem {
         if window_events.is_pressed(winit::event::VirtualKeyCode::Escape) {
             ctx.quit();
         }
-        delta = cam.camera().transform.rotation.euler_into_rotation_matrix3() * delta * dt;
+        delta = cam.camera().transform.rotation().rotate_vector(&delta) * dt;
         let (a, b) = events.mouse_delta();
-        self.theta += (a as f32) * dt * rotate_speed;
-        self.phi += (b as f32) * dt * rotate_speed;
-        *cam.camera_mut().transform.rotation.y_mut() = self.theta;
-        *cam.camera_mut().transform.rotation.x_mut() = self.phi;
-        cam.camera_mut().transform.position += delta;
+        self.theta -= (a as f32) * dt * rotate_speed;
+        self.phi -= (b as f32) * dt * rotate_speed;
+        cam.camera_mut()
+            .transform
+            .set_rotation(Quaternion::from_euler(&[[self.phi, self.theta, 0.0]].into()));
+        *cam.camera_mut().transform.position_mut() += delta;
     }
 }
 
@@ -144,6 +147,7 @@ fn main() {
 
     scene
         .add_async_system(CreateDataSystem)
+        .add_async_system(TransformPropagationSystem)
         .add_async_system(RendererSystem)
         .add_startup_exclusive_system(WindowCreatorSystem::new(
             "nice".to_owned(),